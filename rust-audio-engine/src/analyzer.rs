@@ -10,6 +10,9 @@
 use serde::{Deserialize, Serialize};
 use realfft::RealFftPlanner;
 use rustfft::num_complex::Complex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use crate::biquad::{Biquad, BiquadCascade};
 
 /// Result of pitch detection
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +31,9 @@ pub struct YinParams {
     pub min_frequency: f32,      // Min detectable freq (default 27.5 Hz = A0)
     pub max_frequency: f32,      // Max detectable freq (default 4186 Hz = C8)
     pub sample_rate: u32,        // Audio sample rate (44100 or 48000)
+    /// If set, run a highpass at this cutoff (e.g. ~40 Hz) over the input
+    /// before detection, to kill rumble that can confuse the estimator
+    pub highpass_cutoff: Option<f32>,
 }
 
 impl Default for YinParams {
@@ -37,10 +43,23 @@ impl Default for YinParams {
             min_frequency: 27.5,      // A0
             max_frequency: 4186.0,    // C8
             sample_rate: 44100,
+            highpass_cutoff: None,
         }
     }
 }
 
+/// Apply `params.highpass_cutoff`, if set, returning a filtered copy; otherwise borrow `samples` as-is
+fn apply_optional_highpass<'a>(samples: &'a [f32], sample_rate: u32, highpass_cutoff: Option<f32>) -> std::borrow::Cow<'a, [f32]> {
+    match highpass_cutoff {
+        Some(cutoff) => {
+            let mut buf = samples.to_vec();
+            Biquad::highpass(sample_rate as f32, cutoff, std::f32::consts::FRAC_1_SQRT_2).process(&mut buf);
+            std::borrow::Cow::Owned(buf)
+        }
+        None => std::borrow::Cow::Borrowed(samples),
+    }
+}
+
 /// YIN pitch detection implementation (CPU-based)
 ///
 /// Based on: de Cheveigné, A., & Kawahara, H. (2002).
@@ -66,6 +85,9 @@ pub fn detect_pitch_yin(samples: &[f32], params: &YinParams) -> Option<PitchResu
         return None;
     }
 
+    let samples = apply_optional_highpass(samples, params.sample_rate, params.highpass_cutoff);
+    let samples: &[f32] = &samples;
+
     // Calculate RMS for silence detection
     let rms = calculate_rms(samples);
     if rms < 0.01 {
@@ -126,10 +148,7 @@ pub fn detect_pitch_yin(samples: &[f32], params: &YinParams) -> Option<PitchResu
 
     // Step 4: Parabolic interpolation for sub-sample accuracy
     let better_tau = if tau > 0 && tau < half_buffer - 1 {
-        let s0 = cmnd[tau - 1];
-        let s1 = cmnd[tau];
-        let s2 = cmnd[tau + 1];
-        tau as f32 + (s2 - s0) / (2.0 * (2.0 * s1 - s0 - s2))
+        parabolic_interpolate(tau as f32, cmnd[tau - 1], cmnd[tau], cmnd[tau + 1])
     } else {
         tau as f32
     };
@@ -156,6 +175,17 @@ pub fn detect_pitch_yin(samples: &[f32], params: &YinParams) -> Option<PitchResu
     })
 }
 
+/// Parabolic interpolation for sub-sample peak refinement, shared between
+/// pitch estimators (YIN's CMND minimum, autocorrelation's peak)
+fn parabolic_interpolate(x: f32, s0: f32, s1: f32, s2: f32) -> f32 {
+    let denom = 2.0 * (2.0 * s1 - s0 - s2);
+    if denom.abs() > 1e-12 {
+        x + (s2 - s0) / denom
+    } else {
+        x
+    }
+}
+
 /// Calculate RMS (Root Mean Square) level of audio samples
 fn calculate_rms(samples: &[f32]) -> f32 {
     if samples.is_empty() {
@@ -303,6 +333,200 @@ mod tests {
     }
 }
 
+// ============================================================================
+// AUTOCORRELATION PITCH ESTIMATOR (alternative backend to YIN)
+// ============================================================================
+
+/// Selects which fundamental-frequency estimator `detect_pitch` dispatches to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PitchAlgorithm {
+    /// Difference-function estimator; can octave-error on rich piano tones
+    Yin,
+    /// Autocorrelation peak-picking estimator; a cheaper cross-check for Yin
+    Autocorrelation,
+}
+
+/// Detect the fundamental frequency of `samples` using the selected algorithm
+///
+/// Dispatches to `detect_pitch_yin` or `detect_pitch_autocorr`. Both share
+/// `PitchResult` semantics (RMS silence gate, `frequency_to_midi`, cents
+/// offset), so callers can swap algorithms without changing how they read
+/// the result.
+pub fn detect_pitch(samples: &[f32], params: &YinParams, algo: PitchAlgorithm) -> Option<PitchResult> {
+    match algo {
+        PitchAlgorithm::Yin => detect_pitch_yin(samples, params),
+        PitchAlgorithm::Autocorrelation => detect_pitch_autocorr(samples, params),
+    }
+}
+
+/// Normalized-autocorrelation value a candidate peak must clear to be taken
+/// as the fundamental without scanning any further. Stopping at the first
+/// peak that clears this (rather than the tallest peak in the whole search
+/// window) is what keeps this estimator from octave-erring downward on
+/// harmonically rich tones, where a later peak at 2x the true period can be
+/// taller than the true-period peak.
+const AUTOCORR_VOICING_THRESHOLD: f32 = 0.3;
+
+/// Autocorrelation-based fundamental frequency estimator (CPU)
+///
+/// Removes the DC offset, computes the normalized autocorrelation
+/// `r[tau] = sum_j x[j]*x[j+tau] / r[0]` over the same `min_lag..max_lag`
+/// range YIN searches, skips past the central lobe to its first
+/// zero-crossing, then picks the first local peak beyond it that clears
+/// `AUTOCORR_VOICING_THRESHOLD` (falling back to the strongest peak found if
+/// none does) and refines it with parabolic interpolation. Tends to avoid
+/// the octave errors YIN's difference function can produce on harmonically
+/// rich tones.
+///
+/// # Arguments:
+/// * `samples` - Audio samples (mono, f32, normalized to ±1.0)
+/// * `params` - Shares `YinParams` so both estimators can be swapped freely
+///
+/// # Returns:
+/// * `Some(PitchResult)` if a pitch peak was found
+/// * `None` if no pitch detected (silence, no peak, etc.)
+pub fn detect_pitch_autocorr(samples: &[f32], params: &YinParams) -> Option<PitchResult> {
+    if samples.len() < 2048 {
+        return None;
+    }
+
+    let samples = apply_optional_highpass(samples, params.sample_rate, params.highpass_cutoff);
+    let samples: &[f32] = &samples;
+
+    let rms = calculate_rms(samples);
+    if rms < 0.01 {
+        return None;
+    }
+
+    let min_lag = (params.sample_rate as f32 / params.max_frequency) as usize;
+    let max_lag = (params.sample_rate as f32 / params.min_frequency) as usize;
+    let buffer_size = samples.len().min(8192);
+    let half_buffer = buffer_size / 2;
+
+    if max_lag >= half_buffer || min_lag == 0 {
+        return None;
+    }
+
+    // Remove DC offset over the analysis buffer
+    let mean = samples[..buffer_size].iter().sum::<f32>() / buffer_size as f32;
+    let centered: Vec<f32> = samples[..buffer_size].iter().map(|&s| s - mean).collect();
+
+    let r0: f32 = centered[..half_buffer].iter().map(|&s| s * s).sum();
+    if r0 <= 0.0 {
+        return None;
+    }
+
+    let mut autocorr = vec![0.0f32; half_buffer];
+    for tau in 0..half_buffer {
+        let mut sum = 0.0;
+        for j in 0..half_buffer {
+            sum += centered[j] * centered[j + tau];
+        }
+        autocorr[tau] = sum / r0;
+    }
+
+    // Skip past the central lobe by finding its first zero-crossing
+    let mut zero_cross = min_lag;
+    while zero_cross < max_lag && autocorr[zero_cross] > 0.0 {
+        zero_cross += 1;
+    }
+    while zero_cross < max_lag && autocorr[zero_cross] <= 0.0 {
+        zero_cross += 1;
+    }
+
+    // From there, find the first local peak that clears the voicing
+    // threshold, stopping the scan immediately rather than continuing on to
+    // find a possibly-taller (but octave-wrong) peak further out. Falls back
+    // to the strongest peak found if none clears the threshold.
+    let mut best_tau: Option<usize> = None;
+    let mut best_value = 0.0f32;
+    let mut first_strong_tau: Option<usize> = None;
+    let search_end = max_lag.saturating_sub(1).max(zero_cross);
+    for t in zero_cross.max(1)..search_end {
+        if autocorr[t] > autocorr[t - 1] && autocorr[t] >= autocorr[t + 1] {
+            if autocorr[t] > best_value {
+                best_value = autocorr[t];
+                best_tau = Some(t);
+            }
+            if autocorr[t] >= AUTOCORR_VOICING_THRESHOLD {
+                first_strong_tau = Some(t);
+                break;
+            }
+        }
+    }
+
+    let peak_tau = first_strong_tau.or(best_tau)?;
+
+    let better_tau = if peak_tau > 0 && peak_tau < half_buffer - 1 {
+        parabolic_interpolate(
+            peak_tau as f32,
+            autocorr[peak_tau - 1],
+            autocorr[peak_tau],
+            autocorr[peak_tau + 1],
+        )
+    } else {
+        peak_tau as f32
+    };
+
+    let frequency = params.sample_rate as f32 / better_tau;
+    let confidence = best_value.clamp(0.0, 1.0);
+    let midi_note = frequency_to_midi(frequency);
+    let exact_midi = 69.0 + 12.0 * (frequency / 440.0).log2();
+    let cents_offset = (exact_midi - midi_note as f32) * 100.0;
+
+    Some(PitchResult {
+        frequency,
+        confidence,
+        midi_note,
+        cents_offset,
+        rms_level: rms,
+    })
+}
+
+#[cfg(test)]
+mod autocorr_tests {
+    use super::*;
+
+    fn generate_sine_wave(frequency: f32, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                (2.0 * std::f32::consts::PI * frequency * t).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_autocorr_detects_a440() {
+        let samples = generate_sine_wave(440.0, 44100, 4096);
+        let params = YinParams::default();
+
+        let result = detect_pitch_autocorr(&samples, &params).unwrap();
+
+        assert!((result.frequency - 440.0).abs() < 2.0, "Frequency should be ~440 Hz");
+        assert_eq!(result.midi_note, 69, "MIDI note should be 69 (A4)");
+    }
+
+    #[test]
+    fn test_autocorr_silence_returns_none() {
+        let samples = vec![0.0f32; 4096];
+        let params = YinParams::default();
+
+        assert!(detect_pitch_autocorr(&samples, &params).is_none());
+    }
+
+    #[test]
+    fn test_dispatcher_matches_direct_call() {
+        let samples = generate_sine_wave(261.63, 44100, 4096);
+        let params = YinParams::default();
+
+        let direct = detect_pitch_autocorr(&samples, &params).unwrap();
+        let dispatched = detect_pitch(&samples, &params, PitchAlgorithm::Autocorrelation).unwrap();
+
+        assert_eq!(direct.midi_note, dispatched.midi_note);
+    }
+}
+
 // ============================================================================
 // ONSET DETECTION (STORY-2.2: Rhythm Analysis)
 // ============================================================================
@@ -325,6 +549,9 @@ pub struct OnsetParams {
     pub min_inter_onset: f64,    // Min time between onsets in seconds (default 0.05 = 50ms)
     pub energy_threshold: f32,   // Silence gate (default 0.01)
     pub sample_rate: u32,        // Audio sample rate
+    /// If set, run a highpass at this cutoff (e.g. ~40 Hz) over the input
+    /// before detection, to kill rumble that can confuse the flux detector
+    pub highpass_cutoff: Option<f32>,
 }
 
 impl Default for OnsetParams {
@@ -336,6 +563,7 @@ impl Default for OnsetParams {
             min_inter_onset: 0.05,  // 50ms minimum between note onsets
             energy_threshold: 0.01,
             sample_rate: 44100,
+            highpass_cutoff: None,
         }
     }
 }
@@ -360,6 +588,9 @@ pub fn detect_onsets(samples: &[f32], params: &OnsetParams) -> Vec<OnsetEvent> {
         return Vec::new();
     }
 
+    let samples = apply_optional_highpass(samples, params.sample_rate, params.highpass_cutoff);
+    let samples: &[f32] = &samples;
+
     // Step 1: Compute STFT
     let frames = compute_stft(samples, params.fft_size, params.hop_size);
 
@@ -665,6 +896,110 @@ pub fn analyze_dynamics(
     results
 }
 
+/// Like `analyze_dynamics`, but runs each note segment through the
+/// A-weighting cascade before computing RMS/peak, so `db_level` and
+/// `midi_velocity` track perceived loudness instead of raw, frequency-agnostic
+/// amplitude.
+pub fn analyze_dynamics_weighted(
+    audio: &[f32],
+    onsets: &[OnsetEvent],
+    sample_rate: u32,
+) -> Vec<DynamicsEvent> {
+    let mut results = Vec::with_capacity(onsets.len());
+
+    for i in 0..onsets.len() {
+        let start = onsets[i].sample_index;
+        let end = if i + 1 < onsets.len() {
+            onsets[i + 1].sample_index
+        } else {
+            audio.len()
+        };
+
+        let mut segment = audio[start..end].to_vec();
+        BiquadCascade::a_weighting(sample_rate as f32).process(&mut segment);
+
+        let rms = calculate_rms(&segment);
+        let peak = find_peak(&segment);
+        let db = amplitude_to_db(rms);
+        let velocity = db_to_velocity(db);
+
+        results.push(DynamicsEvent {
+            timestamp: onsets[i].timestamp,
+            rms_level: rms,
+            peak_level: peak,
+            db_level: db,
+            midi_velocity: velocity,
+        });
+    }
+
+    results
+}
+
+/// Smoothing configuration for `analyze_dynamics_smoothed`
+#[derive(Debug, Clone, Copy)]
+pub struct DynamicsConfig {
+    /// EWMA smoothing factor used to derive `weight = 2.0 / (1.0 + n)`.
+    /// Larger values smooth more aggressively; `1.0` gives `weight = 1.0`,
+    /// i.e. no smoothing at all.
+    pub n: f32,
+}
+
+impl Default for DynamicsConfig {
+    fn default() -> Self {
+        Self { n: 1.0 }
+    }
+}
+
+/// Like `analyze_dynamics`, but runs the per-segment dB level through an
+/// exponentially-weighted moving average before deriving MIDI velocity, so
+/// sustained or tremolo passages don't produce velocities that jump around
+/// just because successive onset-segment windows landed unevenly.
+///
+/// `current` is seeded with the first segment's raw dB (no ramp-in
+/// artifact), then updated per segment as
+/// `current = weight * next + (1.0 - weight) * current`.
+pub fn analyze_dynamics_smoothed(
+    audio: &[f32],
+    onsets: &[OnsetEvent],
+    config: &DynamicsConfig,
+) -> Vec<DynamicsEvent> {
+    let mut results = Vec::with_capacity(onsets.len());
+    let weight = 2.0 / (1.0 + config.n);
+
+    let mut current: Option<f32> = None;
+
+    for i in 0..onsets.len() {
+        let start = onsets[i].sample_index;
+        let end = if i + 1 < onsets.len() {
+            onsets[i + 1].sample_index
+        } else {
+            audio.len()
+        };
+
+        let segment = &audio[start..end];
+
+        let rms = calculate_rms(segment);
+        let peak = find_peak(segment);
+        let raw_db = amplitude_to_db(rms);
+
+        let smoothed_db = match current {
+            None => raw_db,
+            Some(prev) => weight * raw_db + (1.0 - weight) * prev,
+        };
+        current = Some(smoothed_db);
+
+        results.push(DynamicsEvent {
+            timestamp: onsets[i].timestamp,
+            rms_level: rms,
+            peak_level: peak,
+            db_level: smoothed_db,
+            midi_velocity: db_to_velocity(smoothed_db),
+        });
+    }
+
+    results
+}
+
 /// Find peak amplitude in audio segment
 fn find_peak(samples: &[f32]) -> f32 {
     samples.iter()
@@ -692,82 +1027,410 @@ fn db_to_velocity(db: f32) -> u8 {
     (normalized.clamp(0.0, 1.0) * 127.0) as u8
 }
 
-#[cfg(test)]
-mod dynamics_tests {
-    use super::*;
+// ============================================================================
+// PERFORMANCE SCORING (STORY-2.4: DTW alignment against an expected MIDI)
+// ============================================================================
 
-    #[test]
-    fn test_rms_sine_wave() {
-        // RMS of sine wave = amplitude / sqrt(2)
-        let amplitude = 0.5;
-        let samples: Vec<f32> = (0..1000)
-            .map(|i| amplitude * (2.0 * std::f32::consts::PI * i as f32 / 100.0).sin())
-            .collect();
+/// A single note in a performance, expected or detected: when it happens and what pitch
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct NoteEvent {
+    pub time: f64,
+    pub midi_note: u8,
+}
 
-        let rms = calculate_rms(&samples);
-        let expected = amplitude / 2.0f32.sqrt();
+/// Per-note comparison between an expected note and its DTW-aligned detected note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteError {
+    pub expected_time: f64,
+    pub expected_note: u8,
+    pub detected_time: Option<f64>,
+    pub detected_note: Option<u8>,
+    pub semitone_error: f32,
+    pub timing_error_seconds: f64,
+}
 
-        assert!((rms - expected).abs() < 0.01, "RMS calculation incorrect: expected {}, got {}", expected, rms);
-    }
+/// Result of scoring a recorded performance against an expected MIDI sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceScore {
+    pub pitch_accuracy: f32,
+    pub rhythm_accuracy: f32,
+    pub note_errors: Vec<NoteError>,
+}
 
-    #[test]
-    fn test_rms_dc_signal() {
-        // RMS of constant signal = absolute value
-        let samples = vec![0.5; 1000];
-        let rms = calculate_rms(&samples);
+/// Penalty applied to an octave-wrong or missing detected note, in "semitones"
+const OCTAVE_OR_MISSING_PENALTY: f32 = 12.0;
 
-        assert!((rms - 0.5).abs() < 0.01, "RMS of DC signal should equal amplitude");
-    }
+/// Pitch tolerance (in semitones) within which a note counts as "matched"
+const PITCH_MATCH_TOLERANCE: f32 = 0.5;
 
-    #[test]
-    fn test_rms_silence() {
-        let samples = vec![0.0; 1000];
-        let rms = calculate_rms(&samples);
+/// Onset-time distance weight relative to pitch distance in the DTW cost
+const TIME_DISTANCE_WEIGHT: f64 = 4.0;
 
-        assert_eq!(rms, 0.0, "RMS of silence should be 0");
-    }
+/// Cap used to normalize mean timing deviation into a 0.0-1.0 rhythm score
+const MAX_EXPECTED_TIMING_DEVIATION_SECONDS: f64 = 0.5;
 
-    #[test]
-    fn test_peak_detection() {
-        let samples = vec![0.1, -0.3, 0.7, -0.5, 0.2];
-        let peak = find_peak(&samples);
+/// Segment a recording into detected `(onset_time, midi_note)` pairs by
+/// running onset detection and then YIN pitch detection on each segment.
+fn detect_note_events(samples: &[f32], sample_rate: u32) -> Vec<NoteEvent> {
+    let onset_params = OnsetParams {
+        sample_rate,
+        ..Default::default()
+    };
+    let onsets = detect_onsets(samples, &onset_params);
 
-        assert_eq!(peak, 0.7, "Peak should be 0.7");
-    }
+    let yin_params = YinParams {
+        sample_rate,
+        ..Default::default()
+    };
 
-    #[test]
-    fn test_db_conversion() {
-        // 0dB = full scale (amplitude 1.0)
-        assert_eq!(amplitude_to_db(1.0), 0.0);
+    let mut events = Vec::with_capacity(onsets.len());
+    for (i, onset) in onsets.iter().enumerate() {
+        let start = onset.sample_index;
+        let end = onsets.get(i + 1).map(|next| next.sample_index).unwrap_or(samples.len());
 
-        // -6dB ≈ 0.5 amplitude (half power)
-        let db_half = amplitude_to_db(0.5);
-        assert!((db_half - (-6.02)).abs() < 0.1, "0.5 amplitude should be ~-6dB");
+        if start >= end {
+            continue;
+        }
 
-        // Silence floor
-        assert_eq!(amplitude_to_db(0.0), -60.0);
-        assert_eq!(amplitude_to_db(1e-7), -60.0);
+        if let Some(pitch) = detect_pitch_yin(&samples[start..end], &yin_params) {
+            events.push(NoteEvent {
+                time: onset.timestamp,
+                midi_note: pitch.midi_note,
+            });
+        }
     }
 
-    #[test]
-    fn test_velocity_mapping() {
-        // 0dB = max velocity
-        assert_eq!(db_to_velocity(0.0), 127);
+    events
+}
 
-        // -60dB = min velocity
-        assert_eq!(db_to_velocity(-60.0), 0);
+/// Cost of aligning expected note `i` against detected note `j`: pitch
+/// distance in semitones (with a large penalty for octave errors) plus a
+/// weighted onset-time distance.
+fn dtw_cost(expected: &NoteEvent, detected: &NoteEvent) -> f64 {
+    let semitone_diff = (expected.midi_note as i32 - detected.midi_note as i32).abs();
+    let pitch_cost = if semitone_diff == 0 {
+        0.0
+    } else if semitone_diff % 12 == 0 {
+        OCTAVE_OR_MISSING_PENALTY as f64
+    } else {
+        semitone_diff as f64
+    };
 
-        // -30dB = mid velocity
-        let mid_vel = db_to_velocity(-30.0);
-        assert!((mid_vel as i32 - 63).abs() <= 1, "Mid velocity should be ~63, got {}", mid_vel);
-    }
+    let time_cost = (expected.time - detected.time).abs() * TIME_DISTANCE_WEIGHT;
 
-    #[test]
-    fn test_velocity_clamping() {
-        // Beyond range should clamp
-        assert_eq!(db_to_velocity(10.0), 127);  // Above 0dB
-        assert_eq!(db_to_velocity(-100.0), 0);  // Below -60dB
-    }
+    pitch_cost + time_cost
+}
+
+/// Align an expected note sequence against a detected note sequence with
+/// dynamic time warping, returning exactly one entry per expected index, in
+/// order: `(expected_index, Some(detected_index))` where a detected note was
+/// matched to it (a "diagonal" step), or `(expected_index, None)` where it
+/// has no match (an "up" step — the expected note was skipped). A detected
+/// note with no expected counterpart (a "left" step) is consumed during
+/// backtracking but never added to the path, since there's no expected note to report it against.
+fn dtw_align(expected: &[NoteEvent], detected: &[NoteEvent]) -> Vec<(usize, Option<usize>)> {
+    let n = expected.len();
+    let m = detected.len();
+
+    if m == 0 {
+        return (0..n).map(|i| (i, None)).collect();
+    }
+    if n == 0 {
+        return Vec::new();
+    }
+
+    // D[i][j] = cost of aligning expected[0..i] against detected[0..j]
+    let mut cost = vec![vec![0.0f64; m + 1]; n + 1];
+    for i in 1..=n {
+        cost[i][0] = f64::INFINITY;
+    }
+    for j in 1..=m {
+        cost[0][j] = f64::INFINITY;
+    }
+
+    for i in 1..=n {
+        for j in 1..=m {
+            let c = dtw_cost(&expected[i - 1], &detected[j - 1]);
+            let best_prev = cost[i - 1][j].min(cost[i][j - 1]).min(cost[i - 1][j - 1]);
+            cost[i][j] = c + best_prev;
+        }
+    }
+
+    // Backtrack from (n, m) to (0, 0), pushing only the step actually taken:
+    // a diagonal match, an "up" skip of an unmatched expected note, or a
+    // "left" skip of an unmatched detected note (not pushed at all — it
+    // isn't associated with any expected index).
+    let mut path = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (n, m);
+    while i > 0 && j > 0 {
+        let diag = cost[i - 1][j - 1];
+        let up = cost[i - 1][j];
+        let left = cost[i][j - 1];
+
+        if diag <= up && diag <= left {
+            path.push((i - 1, Some(j - 1)));
+            i -= 1;
+            j -= 1;
+        } else if up <= left {
+            path.push((i - 1, None));
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+    }
+
+    path.reverse();
+    path
+}
+
+/// Score a recorded performance against the expected note sequence from a
+/// MIDI file, via onset/pitch detection followed by DTW alignment.
+///
+/// # Arguments
+/// * `samples` - Recorded audio (mono, normalized ±1.0)
+/// * `sample_rate` - Sample rate in Hz
+/// * `expected` - Expected `(time, midi_note)` sequence, e.g. from
+///   `MidiSynthesizer::parse_note_events`
+pub fn score_performance(samples: &[f32], sample_rate: u32, expected: &[NoteEvent]) -> PerformanceScore {
+    let detected = detect_note_events(samples, sample_rate);
+    let path = dtw_align(expected, &detected);
+
+    let mut note_errors = Vec::with_capacity(expected.len());
+    let mut matched_within_tolerance = 0usize;
+    let mut timing_deviations = Vec::with_capacity(path.len());
+
+    for (expected_idx, detected_idx) in &path {
+        let exp = &expected[*expected_idx];
+
+        match detected_idx.map(|j| &detected[j]) {
+            Some(det) => {
+                let semitone_error = (exp.midi_note as i32 - det.midi_note as i32).abs() as f32;
+                let timing_error = (exp.time - det.time).abs();
+
+                if semitone_error <= PITCH_MATCH_TOLERANCE {
+                    matched_within_tolerance += 1;
+                }
+                timing_deviations.push(timing_error);
+
+                note_errors.push(NoteError {
+                    expected_time: exp.time,
+                    expected_note: exp.midi_note,
+                    detected_time: Some(det.time),
+                    detected_note: Some(det.midi_note),
+                    semitone_error,
+                    timing_error_seconds: timing_error,
+                });
+            }
+            None => {
+                timing_deviations.push(MAX_EXPECTED_TIMING_DEVIATION_SECONDS);
+                note_errors.push(NoteError {
+                    expected_time: exp.time,
+                    expected_note: exp.midi_note,
+                    detected_time: None,
+                    detected_note: None,
+                    semitone_error: OCTAVE_OR_MISSING_PENALTY,
+                    timing_error_seconds: MAX_EXPECTED_TIMING_DEVIATION_SECONDS,
+                });
+            }
+        }
+    }
+
+    let pitch_accuracy = if expected.is_empty() {
+        0.0
+    } else {
+        matched_within_tolerance as f32 / expected.len() as f32
+    };
+
+    let rhythm_accuracy = if timing_deviations.is_empty() {
+        0.0
+    } else {
+        let mean_deviation =
+            timing_deviations.iter().sum::<f64>() / timing_deviations.len() as f64;
+        let normalized = (mean_deviation / MAX_EXPECTED_TIMING_DEVIATION_SECONDS).min(1.0);
+        (1.0 - normalized) as f32
+    };
+
+    PerformanceScore {
+        pitch_accuracy,
+        rhythm_accuracy,
+        note_errors,
+    }
+}
+
+#[cfg(test)]
+mod performance_scoring_tests {
+    use super::*;
+
+    #[test]
+    fn test_dtw_align_identical_sequences() {
+        let notes = vec![
+            NoteEvent { time: 0.0, midi_note: 60 },
+            NoteEvent { time: 0.5, midi_note: 64 },
+            NoteEvent { time: 1.0, midi_note: 67 },
+        ];
+
+        let path = dtw_align(&notes, &notes);
+
+        assert_eq!(path.len(), 3);
+        for (i, j) in path {
+            assert_eq!(j, Some(i));
+        }
+    }
+
+    #[test]
+    fn test_dtw_align_empty_detected() {
+        let expected = vec![NoteEvent { time: 0.0, midi_note: 60 }];
+        let path = dtw_align(&expected, &[]);
+
+        assert_eq!(path, vec![(0, None)]);
+    }
+
+    #[test]
+    fn test_dtw_cost_octave_penalty() {
+        let a = NoteEvent { time: 0.0, midi_note: 60 };
+        let b = NoteEvent { time: 0.0, midi_note: 72 }; // one octave up
+
+        assert_eq!(dtw_cost(&a, &b), OCTAVE_OR_MISSING_PENALTY as f64);
+    }
+
+    #[test]
+    fn test_dtw_align_one_expected_many_detected_yields_single_entry() {
+        // One expected note, two detected notes that both match its pitch:
+        // the path must still have exactly one entry for expected index 0,
+        // not one per detected note it happens to be close to.
+        let expected = vec![NoteEvent { time: 0.0, midi_note: 60 }];
+        let detected = vec![
+            NoteEvent { time: 0.0, midi_note: 60 },
+            NoteEvent { time: 1.0, midi_note: 60 },
+        ];
+
+        let path = dtw_align(&expected, &detected);
+
+        assert_eq!(path.len(), 1);
+        assert_eq!(path[0].0, 0);
+    }
+
+    #[test]
+    fn test_dtw_align_path_has_exactly_one_entry_per_expected_note() {
+        // More detected notes than expected: extra detected notes must be
+        // absorbed as unmatched "left" steps, never duplicating an expected index.
+        let expected = vec![
+            NoteEvent { time: 0.0, midi_note: 60 },
+            NoteEvent { time: 1.0, midi_note: 64 },
+        ];
+        let detected = vec![
+            NoteEvent { time: 0.0, midi_note: 60 },
+            NoteEvent { time: 0.3, midi_note: 61 }, // spurious extra note
+            NoteEvent { time: 1.0, midi_note: 64 },
+        ];
+
+        let path = dtw_align(&expected, &detected);
+
+        assert_eq!(path.len(), expected.len());
+        let expected_indices: Vec<usize> = path.iter().map(|(i, _)| *i).collect();
+        assert_eq!(expected_indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_score_performance_does_not_overcount_pitch_matches() {
+        // Silent audio yields no detected notes; the score must still hold
+        // exactly one note error per expected note and keep pitch_accuracy
+        // within 1.0. The one-expected/two-detected alignment case is covered
+        // by test_dtw_align_one_expected_many_detected_yields_single_entry.
+        let sample_rate = 44100;
+        let samples = vec![0.0f32; sample_rate as usize];
+        let expected = vec![NoteEvent { time: 0.0, midi_note: 60 }];
+
+        let score = score_performance(&samples, sample_rate, &expected);
+
+        assert!(score.pitch_accuracy <= 1.0);
+        assert_eq!(score.note_errors.len(), expected.len());
+    }
+
+    #[test]
+    fn test_score_performance_no_expected_notes() {
+        let score = score_performance(&[0.0; 4096], 44100, &[]);
+        assert_eq!(score.pitch_accuracy, 0.0);
+        assert!(score.note_errors.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod dynamics_tests {
+    use super::*;
+
+    #[test]
+    fn test_rms_sine_wave() {
+        // RMS of sine wave = amplitude / sqrt(2)
+        let amplitude = 0.5;
+        let samples: Vec<f32> = (0..1000)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * i as f32 / 100.0).sin())
+            .collect();
+
+        let rms = calculate_rms(&samples);
+        let expected = amplitude / 2.0f32.sqrt();
+
+        assert!((rms - expected).abs() < 0.01, "RMS calculation incorrect: expected {}, got {}", expected, rms);
+    }
+
+    #[test]
+    fn test_rms_dc_signal() {
+        // RMS of constant signal = absolute value
+        let samples = vec![0.5; 1000];
+        let rms = calculate_rms(&samples);
+
+        assert!((rms - 0.5).abs() < 0.01, "RMS of DC signal should equal amplitude");
+    }
+
+    #[test]
+    fn test_rms_silence() {
+        let samples = vec![0.0; 1000];
+        let rms = calculate_rms(&samples);
+
+        assert_eq!(rms, 0.0, "RMS of silence should be 0");
+    }
+
+    #[test]
+    fn test_peak_detection() {
+        let samples = vec![0.1, -0.3, 0.7, -0.5, 0.2];
+        let peak = find_peak(&samples);
+
+        assert_eq!(peak, 0.7, "Peak should be 0.7");
+    }
+
+    #[test]
+    fn test_db_conversion() {
+        // 0dB = full scale (amplitude 1.0)
+        assert_eq!(amplitude_to_db(1.0), 0.0);
+
+        // -6dB ≈ 0.5 amplitude (half power)
+        let db_half = amplitude_to_db(0.5);
+        assert!((db_half - (-6.02)).abs() < 0.1, "0.5 amplitude should be ~-6dB");
+
+        // Silence floor
+        assert_eq!(amplitude_to_db(0.0), -60.0);
+        assert_eq!(amplitude_to_db(1e-7), -60.0);
+    }
+
+    #[test]
+    fn test_velocity_mapping() {
+        // 0dB = max velocity
+        assert_eq!(db_to_velocity(0.0), 127);
+
+        // -60dB = min velocity
+        assert_eq!(db_to_velocity(-60.0), 0);
+
+        // -30dB = mid velocity
+        let mid_vel = db_to_velocity(-30.0);
+        assert!((mid_vel as i32 - 63).abs() <= 1, "Mid velocity should be ~63, got {}", mid_vel);
+    }
+
+    #[test]
+    fn test_velocity_clamping() {
+        // Beyond range should clamp
+        assert_eq!(db_to_velocity(10.0), 127);  // Above 0dB
+        assert_eq!(db_to_velocity(-100.0), 0);  // Below -60dB
+    }
 
     #[test]
     fn test_analyze_dynamics_basic() {
@@ -818,3 +1481,1915 @@ mod dynamics_tests {
         assert!(dynamics[1].midi_velocity < dynamics[2].midi_velocity);
     }
 }
+
+#[cfg(test)]
+mod dynamics_smoothing_tests {
+    use super::*;
+
+    fn onsets_with_samples(sample_indices: &[usize]) -> Vec<OnsetEvent> {
+        sample_indices
+            .iter()
+            .map(|&idx| OnsetEvent {
+                timestamp: idx as f64 / 44100.0,
+                sample_index: idx,
+                strength: 1.0,
+                confidence: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_no_smoothing_matches_raw_dynamics_when_n_is_one() {
+        let mut audio = Vec::new();
+        audio.extend(vec![0.1; 4410]);
+        audio.extend(vec![0.9; 4410]);
+
+        let onsets = onsets_with_samples(&[0, 4410]);
+        let raw = analyze_dynamics(&audio, &onsets, 44100);
+        let smoothed = analyze_dynamics_smoothed(&audio, &onsets, &DynamicsConfig::default());
+
+        for (r, s) in raw.iter().zip(smoothed.iter()) {
+            assert!((r.db_level - s.db_level).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_smoothing_has_no_ramp_in_artifact() {
+        let audio = vec![0.5; 8820];
+        let onsets = onsets_with_samples(&[0]);
+
+        let config = DynamicsConfig { n: 5.0 };
+        let smoothed = analyze_dynamics_smoothed(&audio, &onsets, &config);
+        let raw = analyze_dynamics(&audio, &onsets, 44100);
+
+        assert!((smoothed[0].db_level - raw[0].db_level).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_smoothing_dampens_a_single_loud_spike() {
+        let mut audio = Vec::new();
+        audio.extend(vec![0.1; 4410]); // soft
+        audio.extend(vec![0.9; 4410]); // one loud spike
+        audio.extend(vec![0.1; 4410]); // back to soft
+
+        let onsets = onsets_with_samples(&[0, 4410, 8820]);
+
+        let config = DynamicsConfig { n: 10.0 };
+        let smoothed = analyze_dynamics_smoothed(&audio, &onsets, &config);
+        let raw = analyze_dynamics(&audio, &onsets, 44100);
+
+        // The spike should be smoothed toward the surrounding level, so its
+        // smoothed dB rises less sharply than the raw window's dB
+        assert!(smoothed[1].db_level < raw[1].db_level);
+    }
+}
+
+// ============================================================================
+// VELOCITY RESPONSE CURVES
+// ============================================================================
+
+/// A velocity-response curve mapping a dB level to a MIDI velocity (0-127)
+#[derive(Debug, Clone, PartialEq)]
+pub enum VelocityCurve {
+    /// Linear map: `floor_db` -> 0, `ceiling_db` -> 127 (matches `db_to_velocity`)
+    Linear,
+    /// `normalized.powf(gamma)` before scaling to 0-127; `gamma > 1.0` pushes
+    /// mid-level notes softer, `gamma < 1.0` pushes them louder
+    Exponential { gamma: f32 },
+    /// Log-weighted map that emphasizes low-level detail and compresses the top end
+    Logarithmic,
+    /// Breakpoint table of `(db, velocity)` pairs, linearly interpolated
+    /// between the two nearest points and clamped to the table's endpoints
+    /// outside its range
+    Custom(Vec<(f32, u8)>),
+}
+
+/// Configuration for `db_to_velocity_curved` / `analyze_dynamics_with_curve`
+#[derive(Debug, Clone)]
+pub struct VelocityCurveConfig {
+    pub curve: VelocityCurve,
+    pub floor_db: f32,
+    pub ceiling_db: f32,
+}
+
+impl Default for VelocityCurveConfig {
+    fn default() -> Self {
+        Self {
+            curve: VelocityCurve::Linear,
+            floor_db: -60.0,
+            ceiling_db: 0.0,
+        }
+    }
+}
+
+/// Map a dB level to a MIDI velocity using a configurable response curve
+pub fn db_to_velocity_curved(db: f32, config: &VelocityCurveConfig) -> u8 {
+    if let VelocityCurve::Custom(points) = &config.curve {
+        return interpolate_velocity_breakpoints(points, db);
+    }
+
+    let range = (config.ceiling_db - config.floor_db).max(1e-6);
+    let normalized = ((db - config.floor_db) / range).clamp(0.0, 1.0);
+
+    let shaped = match &config.curve {
+        VelocityCurve::Linear => normalized,
+        VelocityCurve::Exponential { gamma } => normalized.powf(*gamma),
+        // log1p-style curve over [0, 1]: emphasizes low-level detail
+        VelocityCurve::Logarithmic => (1.0 + normalized * (std::f32::consts::E - 1.0)).ln(),
+        VelocityCurve::Custom(_) => unreachable!("handled above"),
+    };
+
+    (shaped.clamp(0.0, 1.0) * 127.0) as u8
+}
+
+/// Linearly interpolate a `(db, velocity)` breakpoint table, clamped to the
+/// table's endpoints outside its range
+fn interpolate_velocity_breakpoints(points: &[(f32, u8)], db: f32) -> u8 {
+    if points.is_empty() {
+        return 0;
+    }
+
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    if db <= sorted[0].0 {
+        return sorted[0].1;
+    }
+    if db >= sorted[sorted.len() - 1].0 {
+        return sorted[sorted.len() - 1].1;
+    }
+
+    for window in sorted.windows(2) {
+        let (db0, v0) = window[0];
+        let (db1, v1) = window[1];
+        if db >= db0 && db <= db1 {
+            let t = if (db1 - db0).abs() > 1e-9 {
+                (db - db0) / (db1 - db0)
+            } else {
+                0.0
+            };
+            let value = v0 as f32 + t * (v1 as f32 - v0 as f32);
+            return value.round().clamp(0.0, 127.0) as u8;
+        }
+    }
+
+    sorted[sorted.len() - 1].1
+}
+
+/// Like `analyze_dynamics`, but maps each segment's dB level to a MIDI
+/// velocity through a configurable `VelocityCurve` instead of the fixed
+/// linear `-60..0` dB mapping, so output velocities can be shaped to match a
+/// given piano/soundfont's response. `VelocityCurveConfig::default()`
+/// reproduces `analyze_dynamics`'s existing linear behavior exactly.
+pub fn analyze_dynamics_with_curve(
+    audio: &[f32],
+    onsets: &[OnsetEvent],
+    config: &VelocityCurveConfig,
+) -> Vec<DynamicsEvent> {
+    let mut results = Vec::with_capacity(onsets.len());
+
+    for i in 0..onsets.len() {
+        let start = onsets[i].sample_index;
+        let end = if i + 1 < onsets.len() {
+            onsets[i + 1].sample_index
+        } else {
+            audio.len()
+        };
+
+        let segment = &audio[start..end];
+
+        let rms = calculate_rms(segment);
+        let peak = find_peak(segment);
+        let db = amplitude_to_db(rms);
+        let velocity = db_to_velocity_curved(db, config);
+
+        results.push(DynamicsEvent {
+            timestamp: onsets[i].timestamp,
+            rms_level: rms,
+            peak_level: peak,
+            db_level: db,
+            midi_velocity: velocity,
+        });
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod velocity_curve_tests {
+    use super::*;
+
+    fn onsets_with_samples(sample_indices: &[usize]) -> Vec<OnsetEvent> {
+        sample_indices
+            .iter()
+            .map(|&idx| OnsetEvent {
+                timestamp: idx as f64 / 44100.0,
+                sample_index: idx,
+                strength: 1.0,
+                confidence: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_default_linear_curve_matches_legacy_db_to_velocity() {
+        let mut audio = Vec::new();
+        audio.extend(vec![0.1; 4410]);
+        audio.extend(vec![0.5; 4410]);
+        audio.extend(vec![0.9; 4410]);
+
+        let onsets = onsets_with_samples(&[0, 4410, 8820]);
+        let legacy = analyze_dynamics(&audio, &onsets, 44100);
+        let curved = analyze_dynamics_with_curve(&audio, &onsets, &VelocityCurveConfig::default());
+
+        for (l, c) in legacy.iter().zip(curved.iter()) {
+            assert_eq!(l.midi_velocity, c.midi_velocity);
+        }
+    }
+
+    #[test]
+    fn test_gamma_greater_than_one_pushes_midlevel_notes_softer() {
+        let mid_db = -30.0;
+
+        let linear = db_to_velocity_curved(mid_db, &VelocityCurveConfig::default());
+        let exponential = db_to_velocity_curved(
+            mid_db,
+            &VelocityCurveConfig {
+                curve: VelocityCurve::Exponential { gamma: 2.0 },
+                ..Default::default()
+            },
+        );
+
+        assert!(exponential < linear, "gamma > 1 should map mid-level dB to a softer velocity");
+    }
+
+    #[test]
+    fn test_custom_breakpoints_interpolate_between_points() {
+        let config = VelocityCurveConfig {
+            curve: VelocityCurve::Custom(vec![(-60.0, 0), (-30.0, 64), (0.0, 127)]),
+            ..Default::default()
+        };
+
+        let mid = db_to_velocity_curved(-30.0, &config);
+        assert_eq!(mid, 64);
+
+        let quarter = db_to_velocity_curved(-45.0, &config);
+        assert!(quarter > 0 && quarter < 64, "interpolated point should sit strictly between its neighbors");
+    }
+
+    #[test]
+    fn test_custom_breakpoints_clamp_outside_table_range() {
+        let config = VelocityCurveConfig {
+            curve: VelocityCurve::Custom(vec![(-40.0, 10), (-10.0, 100)]),
+            ..Default::default()
+        };
+
+        assert_eq!(db_to_velocity_curved(-60.0, &config), 10);
+        assert_eq!(db_to_velocity_curved(0.0, &config), 100);
+    }
+}
+
+// ============================================================================
+// VELOCITY HUMANIZATION
+// ============================================================================
+
+/// Minimal seedable PRNG (xorshift64*), used to humanize velocities
+/// deterministically without pulling in an external RNG crate for one
+/// uniform-draw use site.
+struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    fn new(seed: u64) -> Self {
+        // xorshift64* requires a non-zero state
+        Self {
+            state: if seed == 0 { 0x9E3779B97F4A7C15 } else { seed },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform float in `[0.0, 1.0)`
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Parameters for velocity humanization
+#[derive(Debug, Clone, Copy)]
+pub struct HumanizeParams {
+    pub min_vel: u8,
+    pub max_vel: u8,
+    /// How strongly the humanized velocity is pulled toward the detected
+    /// value (`1.0`) vs. a uniform random draw across `[min_vel, max_vel)` (`0.0`)
+    pub weight: f32,
+    /// RNG seed, for reproducible re-renders
+    pub seed: u64,
+}
+
+impl Default for HumanizeParams {
+    fn default() -> Self {
+        Self {
+            min_vel: 1,
+            max_vel: 127,
+            weight: 0.8,
+            seed: 0,
+        }
+    }
+}
+
+/// Perturb detected velocities toward a weighted random offset so
+/// transcribed parts sound less mechanical.
+///
+/// Returns a new vector of humanized velocities parallel to `dynamics`,
+/// leaving each `DynamicsEvent`'s own `midi_velocity`/`rms_level` untouched
+/// so downstream code can still use the originally detected value.
+pub fn humanize_velocities(dynamics: &[DynamicsEvent], params: &HumanizeParams) -> Vec<u8> {
+    let mut rng = SeededRng::new(params.seed);
+    let range = (params.max_vel as f32 - params.min_vel as f32).max(0.0);
+
+    dynamics
+        .iter()
+        .map(|event| {
+            let r = params.min_vel as f32 + rng.next_f32() * range;
+            let detected = event.midi_velocity as f32;
+            let humanized = params.weight * detected + (1.0 - params.weight) * r;
+            humanized.round().clamp(params.min_vel as f32, params.max_vel as f32) as u8
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod humanize_tests {
+    use super::*;
+
+    fn sample_dynamics(velocities: &[u8]) -> Vec<DynamicsEvent> {
+        velocities
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| DynamicsEvent {
+                timestamp: i as f64,
+                rms_level: 0.5,
+                peak_level: 0.5,
+                db_level: -10.0,
+                midi_velocity: v,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weight_one_returns_detected_velocity_exactly() {
+        let dynamics = sample_dynamics(&[20, 60, 100]);
+        let params = HumanizeParams {
+            weight: 1.0,
+            ..Default::default()
+        };
+
+        let humanized = humanize_velocities(&dynamics, &params);
+        assert_eq!(humanized, vec![20, 60, 100]);
+    }
+
+    #[test]
+    fn test_same_seed_is_reproducible() {
+        let dynamics = sample_dynamics(&[10, 50, 90, 30]);
+        let params = HumanizeParams {
+            weight: 0.5,
+            seed: 42,
+            ..Default::default()
+        };
+
+        let first = humanize_velocities(&dynamics, &params);
+        let second = humanize_velocities(&dynamics, &params);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_different_seeds_can_differ() {
+        let dynamics = sample_dynamics(&[10, 50, 90, 30, 64, 12, 99, 5]);
+        let low_weight = HumanizeParams {
+            weight: 0.0,
+            seed: 1,
+            ..Default::default()
+        };
+        let a = humanize_velocities(&dynamics, &low_weight);
+        let b = humanize_velocities(&dynamics, &HumanizeParams { seed: 2, ..low_weight });
+
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_humanized_velocities_stay_within_clamp_range() {
+        let dynamics = sample_dynamics(&[0, 127, 64, 1, 126]);
+        let params = HumanizeParams {
+            min_vel: 10,
+            max_vel: 100,
+            weight: 0.3,
+            seed: 7,
+        };
+
+        for &v in &humanize_velocities(&dynamics, &params) {
+            assert!((10..=100).contains(&v));
+        }
+    }
+}
+
+// ============================================================================
+// PER-ONSET PITCH DETECTION (fuses pitch into the onset + dynamics pipeline)
+// ============================================================================
+
+/// Frequency range searched by `detect_onset_pitch`
+const ONSET_PITCH_MIN_FREQ: f32 = 50.0;
+const ONSET_PITCH_MAX_FREQ: f32 = 2000.0;
+
+/// Voicing threshold for the cumulative-mean-normalized difference function;
+/// a dip below this is accepted as a real pitch rather than noise
+const ONSET_PITCH_CONFIDENCE_THRESHOLD: f32 = 0.1;
+
+/// Detect a single monophonic pitch in the window immediately following an
+/// onset, via the cumulative-mean-normalized difference function (the same
+/// technique as `detect_pitch_yin`, applied locally to one onset's window):
+/// for each lag `tau`, `d(tau) = sum((x[i] - x[i+tau])^2)`, normalized by its
+/// cumulative mean to get `cmndf(tau)`, then the first local minimum below
+/// `ONSET_PITCH_CONFIDENCE_THRESHOLD` is parabolically refined and converted
+/// to Hz.
+///
+/// Returns `None` (unvoiced) if no dip in the difference function clears the
+/// threshold, e.g. on percussive or noisy onsets.
+pub fn detect_onset_pitch(audio: &[f32], onset_sample_index: usize, sample_rate: u32) -> Option<f32> {
+    detect_onset_pitch_with_confidence(audio, onset_sample_index, sample_rate).map(|(freq, _)| freq)
+}
+
+/// As `detect_onset_pitch`, but also returns the voicing confidence
+/// (`1.0 - cmndf` at the chosen lag) so callers can fuse it with dynamics
+/// without recomputing the difference function.
+fn detect_onset_pitch_with_confidence(
+    audio: &[f32],
+    onset_sample_index: usize,
+    sample_rate: u32,
+) -> Option<(f32, f32)> {
+    if onset_sample_index >= audio.len() {
+        return None;
+    }
+
+    let min_lag = (sample_rate as f32 / ONSET_PITCH_MAX_FREQ) as usize;
+    let max_lag = (sample_rate as f32 / ONSET_PITCH_MIN_FREQ) as usize;
+
+    let window = &audio[onset_sample_index..];
+    let window_size = (max_lag * 2).min(window.len());
+    if window_size == 0 || max_lag >= window_size {
+        return None;
+    }
+    let window = &window[..window_size];
+
+    if calculate_rms(window) < 0.01 {
+        return None;
+    }
+
+    // Cumulative-mean-normalized difference function
+    let mut diff = vec![0.0f32; max_lag + 1];
+    for tau in 1..=max_lag {
+        let mut sum = 0.0f32;
+        for i in 0..(window_size - tau) {
+            let delta = window[i] - window[i + tau];
+            sum += delta * delta;
+        }
+        diff[tau] = sum;
+    }
+
+    let mut cmndf = vec![1.0f32; max_lag + 1];
+    let mut running_sum = 0.0f32;
+    for tau in 1..=max_lag {
+        running_sum += diff[tau];
+        cmndf[tau] = if running_sum > 0.0 {
+            diff[tau] * tau as f32 / running_sum
+        } else {
+            1.0
+        };
+    }
+
+    // First local minimum below the voicing threshold
+    let mut tau = min_lag.max(1);
+    while tau < max_lag {
+        if cmndf[tau] < ONSET_PITCH_CONFIDENCE_THRESHOLD {
+            while tau + 1 < max_lag && cmndf[tau + 1] < cmndf[tau] {
+                tau += 1;
+            }
+            break;
+        }
+        tau += 1;
+    }
+
+    if tau >= max_lag || cmndf[tau] >= ONSET_PITCH_CONFIDENCE_THRESHOLD {
+        return None;
+    }
+
+    let refined_tau = if tau > 0 && tau < max_lag {
+        parabolic_interpolate(tau as f32, cmndf[tau - 1], cmndf[tau], cmndf[tau + 1])
+    } else {
+        tau as f32
+    };
+
+    let frequency = sample_rate as f32 / refined_tau;
+    let confidence = 1.0 - cmndf[tau].min(1.0);
+
+    Some((frequency, confidence))
+}
+
+/// A fused note event: onset timing, the detected pitch (if voiced), and
+/// loudness, so downstream code gets full pitch+velocity notes instead of
+/// velocity-only dynamics events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PitchedNoteEvent {
+    pub timestamp: f64,
+    pub midi_note: Option<u8>,
+    pub frequency: Option<f32>,
+    /// Voicing confidence (0.0-1.0); 0.0 when unvoiced
+    pub confidence: f32,
+    pub midi_velocity: u8,
+    pub rms_level: f32,
+}
+
+/// Fuse onsets, per-onset pitch detection, and dynamics into one sequence of
+/// `PitchedNoteEvent`s, one per onset.
+///
+/// `dynamics` is expected to be `analyze_dynamics(audio, onsets, sample_rate)`
+/// (or one of its variants) run over the same `onsets`, so it lines up
+/// index-for-index; if it's shorter, trailing onsets get velocity 0.
+pub fn detect_pitched_note_events(
+    audio: &[f32],
+    onsets: &[OnsetEvent],
+    dynamics: &[DynamicsEvent],
+    sample_rate: u32,
+) -> Vec<PitchedNoteEvent> {
+    onsets
+        .iter()
+        .enumerate()
+        .map(|(i, onset)| {
+            let pitch = detect_onset_pitch_with_confidence(audio, onset.sample_index, sample_rate);
+
+            let (frequency, midi_note, confidence) = match pitch {
+                Some((freq, conf)) => (Some(freq), Some(frequency_to_midi(freq)), conf),
+                None => (None, None, 0.0),
+            };
+
+            let (midi_velocity, rms_level) = dynamics
+                .get(i)
+                .map(|d| (d.midi_velocity, d.rms_level))
+                .unwrap_or((0, 0.0));
+
+            PitchedNoteEvent {
+                timestamp: onset.timestamp,
+                midi_note,
+                frequency,
+                confidence,
+                midi_velocity,
+                rms_level,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod onset_pitch_tests {
+    use super::*;
+
+    fn sine_wave(frequency: f32, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_onset_pitch_sine_wave() {
+        let sample_rate = 44100;
+        let samples = sine_wave(440.0, sample_rate, 8192);
+
+        let frequency = detect_onset_pitch(&samples, 0, sample_rate).expect("should detect pitch");
+        assert!((frequency - 440.0).abs() < 5.0, "Expected ~440Hz, got {}", frequency);
+    }
+
+    #[test]
+    fn test_detect_onset_pitch_silence_is_unvoiced() {
+        let samples = vec![0.0f32; 8192];
+        assert!(detect_onset_pitch(&samples, 0, 44100).is_none());
+    }
+
+    #[test]
+    fn test_detect_onset_pitch_out_of_bounds_index() {
+        let samples = vec![0.1f32; 100];
+        assert!(detect_onset_pitch(&samples, 1000, 44100).is_none());
+    }
+
+    #[test]
+    fn test_detect_pitched_note_events_fuses_pitch_and_velocity() {
+        let sample_rate = 44100;
+        let mut audio = sine_wave(440.0, sample_rate, 8192);
+        audio.extend(vec![0.0f32; 100]); // trailing silence so segment 2 is unvoiced silence
+
+        let onsets = vec![
+            OnsetEvent { timestamp: 0.0, sample_index: 0, strength: 1.0, confidence: 1.0 },
+            OnsetEvent { timestamp: 0.1, sample_index: 8192, strength: 1.0, confidence: 1.0 },
+        ];
+        let dynamics = analyze_dynamics(&audio, &onsets, sample_rate);
+
+        let notes = detect_pitched_note_events(&audio, &onsets, &dynamics, sample_rate);
+
+        assert_eq!(notes.len(), 2);
+        assert!(notes[0].midi_note.is_some());
+        assert!(notes[0].midi_velocity > 0);
+        assert!(notes[1].midi_note.is_none());
+    }
+}
+
+// ============================================================================
+// SAMPLE-RATE-AGNOSTIC FRONT END
+// ============================================================================
+
+/// Parameters for the sample-rate-agnostic analysis front end
+#[derive(Debug, Clone, Copy)]
+pub struct FrontEndParams {
+    /// Sample rate the onset/dynamics window sizes and dB mapping were tuned
+    /// against. Input audio above this rate is downsampled to it before
+    /// analysis; input at or below it is left at its native rate.
+    pub max_samplerate: u32,
+}
+
+impl Default for FrontEndParams {
+    fn default() -> Self {
+        Self { max_samplerate: 44100 }
+    }
+}
+
+/// Resample a mono audio buffer from `src_rate` to `dst_rate`, using the same
+/// windowed-sinc (Lanczos) interpolation as the output stage's `resample`.
+pub fn resample_to(audio: &[f32], src_rate: u32, dst_rate: u32) -> Vec<f32> {
+    crate::output::resample(audio, 1, src_rate, dst_rate).unwrap_or_else(|_| audio.to_vec())
+}
+
+/// Cap `audio` to `params.max_samplerate`: downsample if it arrives
+/// oversampled, otherwise leave it untouched. Returns the (possibly
+/// resampled) audio alongside the rate it's now at.
+pub fn prepare_for_analysis(audio: &[f32], src_rate: u32, params: &FrontEndParams) -> (Vec<f32>, u32) {
+    if src_rate <= params.max_samplerate {
+        (audio.to_vec(), src_rate)
+    } else {
+        (resample_to(audio, src_rate, params.max_samplerate), params.max_samplerate)
+    }
+}
+
+/// Onsets and dynamics detected against the same sample-rate-capped audio
+pub struct CappedAnalysis {
+    pub onsets: Vec<OnsetEvent>,
+    pub dynamics: Vec<DynamicsEvent>,
+    /// Rate `onsets`/`dynamics` were actually computed at, after capping
+    pub rate: u32,
+}
+
+/// Run onset detection and dynamics analysis through a single sample-rate
+/// cap, so window sizes (FFT bins, hop size) and the dB mapping behave
+/// consistently regardless of the caller's native rate, instead of each
+/// analysis stage independently mis-sizing its windows for oversampled input.
+pub fn analyze_with_samplerate_cap(
+    audio: &[f32],
+    src_rate: u32,
+    onset_params: &OnsetParams,
+    front_end: &FrontEndParams,
+) -> CappedAnalysis {
+    let (prepared, rate) = prepare_for_analysis(audio, src_rate, front_end);
+
+    let params = OnsetParams {
+        sample_rate: rate,
+        ..onset_params.clone()
+    };
+    let onsets = detect_onsets(&prepared, &params);
+    let dynamics = analyze_dynamics(&prepared, &onsets, rate);
+
+    CappedAnalysis { onsets, dynamics, rate }
+}
+
+#[cfg(test)]
+mod front_end_tests {
+    use super::*;
+
+    fn sine_wave(frequency: f32, amplitude: f32, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_prepare_for_analysis_leaves_rate_at_or_below_cap_untouched() {
+        let audio = sine_wave(440.0, 0.5, 22050, 2205);
+        let (prepared, rate) = prepare_for_analysis(&audio, 22050, &FrontEndParams::default());
+
+        assert_eq!(rate, 22050);
+        assert_eq!(prepared.len(), audio.len());
+    }
+
+    #[test]
+    fn test_prepare_for_analysis_downsamples_oversampled_input() {
+        let audio = sine_wave(440.0, 0.5, 96000, 9600);
+        let (prepared, rate) = prepare_for_analysis(&audio, 96000, &FrontEndParams::default());
+
+        assert_eq!(rate, 44100);
+        let expected_frames = (9600.0 * 44100.0 / 96000.0).round() as usize;
+        assert_eq!(prepared.len(), expected_frames);
+    }
+
+    #[test]
+    fn test_48khz_dynamics_match_native_44100_within_tolerance() {
+        let single_onset = vec![OnsetEvent {
+            timestamp: 0.0,
+            sample_index: 0,
+            strength: 1.0,
+            confidence: 1.0,
+        }];
+
+        let native_44100 = sine_wave(440.0, 0.5, 44100, 44100);
+        let native_dynamics = analyze_dynamics(&native_44100, &single_onset, 44100);
+
+        let source_48000 = sine_wave(440.0, 0.5, 48000, 48000);
+        let resampled = resample_to(&source_48000, 48000, 44100);
+        let resampled_dynamics = analyze_dynamics(&resampled, &single_onset, 44100);
+
+        let native_db = native_dynamics[0].db_level;
+        let resampled_db = resampled_dynamics[0].db_level;
+        assert!(
+            (native_db - resampled_db).abs() < 0.5,
+            "expected dB levels within tolerance: native={}, resampled={}",
+            native_db,
+            resampled_db
+        );
+    }
+}
+
+// ============================================================================
+// CONTINUOUS EXPRESSION (CC11) ENVELOPE
+// ============================================================================
+
+/// Hop size (in samples) used to sample the RMS envelope for CC11 generation
+const EXPRESSION_HOP_SIZE: usize = 512;
+
+/// A single MIDI Expression (CC11) sample
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ExpressionEvent {
+    pub timestamp: f64,
+    pub cc_value: u8,
+}
+
+/// What `analyze_note_expression` emits for each note
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpressionMode {
+    /// A single velocity per note, same as `analyze_dynamics`
+    VelocityOnly,
+    /// A continuous CC11 expression envelope only
+    Cc11Only,
+    /// Both a per-note velocity and a CC11 envelope
+    Both,
+}
+
+/// Per-note expression: the onset's own velocity and/or its intra-note CC11 envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteExpression {
+    pub timestamp: f64,
+    pub midi_velocity: Option<u8>,
+    pub cc11_envelope: Vec<ExpressionEvent>,
+}
+
+/// Analyze dynamics per note region between onsets, optionally emitting a
+/// time-sampled CC11 expression envelope alongside (or instead of) a single
+/// flat velocity, so crescendos/decrescendos on held chords and swells
+/// survive into the MIDI output instead of collapsing to one level.
+///
+/// The envelope samples short-hop RMS over the note's duration, maps each
+/// hop through `db_to_velocity`, and emits `(timestamp, cc_value)` pairs,
+/// deduplicated when the value is unchanged from the previous hop.
+pub fn analyze_note_expression(
+    audio: &[f32],
+    onsets: &[OnsetEvent],
+    sample_rate: u32,
+    mode: ExpressionMode,
+) -> Vec<NoteExpression> {
+    let mut results = Vec::with_capacity(onsets.len());
+
+    for i in 0..onsets.len() {
+        let start = onsets[i].sample_index;
+        let end = if i + 1 < onsets.len() {
+            onsets[i + 1].sample_index
+        } else {
+            audio.len()
+        };
+        let segment = &audio[start..end];
+
+        let midi_velocity = if matches!(mode, ExpressionMode::VelocityOnly | ExpressionMode::Both) {
+            Some(db_to_velocity(amplitude_to_db(calculate_rms(segment))))
+        } else {
+            None
+        };
+
+        let cc11_envelope = if matches!(mode, ExpressionMode::Cc11Only | ExpressionMode::Both) {
+            compute_cc11_envelope(segment, start, sample_rate)
+        } else {
+            Vec::new()
+        };
+
+        results.push(NoteExpression {
+            timestamp: onsets[i].timestamp,
+            midi_velocity,
+            cc11_envelope,
+        });
+    }
+
+    results
+}
+
+/// Sample the RMS envelope of a note segment in `EXPRESSION_HOP_SIZE` hops,
+/// mapping each hop to a CC11 value and dropping hops that didn't change it
+fn compute_cc11_envelope(segment: &[f32], segment_start_sample: usize, sample_rate: u32) -> Vec<ExpressionEvent> {
+    let mut events = Vec::new();
+    let mut last_value: Option<u8> = None;
+
+    let mut hop_start = 0;
+    while hop_start < segment.len() {
+        let hop_end = (hop_start + EXPRESSION_HOP_SIZE).min(segment.len());
+        let hop = &segment[hop_start..hop_end];
+
+        let value = db_to_velocity(amplitude_to_db(calculate_rms(hop)));
+
+        if last_value != Some(value) {
+            events.push(ExpressionEvent {
+                timestamp: (segment_start_sample + hop_start) as f64 / sample_rate as f64,
+                cc_value: value,
+            });
+            last_value = Some(value);
+        }
+
+        hop_start = hop_end;
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod expression_tests {
+    use super::*;
+
+    fn single_onset() -> Vec<OnsetEvent> {
+        vec![OnsetEvent {
+            timestamp: 0.0,
+            sample_index: 0,
+            strength: 1.0,
+            confidence: 1.0,
+        }]
+    }
+
+    #[test]
+    fn test_velocity_only_mode_emits_no_envelope() {
+        let audio = vec![0.5f32; 4410];
+        let result = analyze_note_expression(&audio, &single_onset(), 44100, ExpressionMode::VelocityOnly);
+
+        assert_eq!(result.len(), 1);
+        assert!(result[0].midi_velocity.is_some());
+        assert!(result[0].cc11_envelope.is_empty());
+    }
+
+    #[test]
+    fn test_cc11_only_mode_emits_no_velocity() {
+        let audio = vec![0.5f32; 4410];
+        let result = analyze_note_expression(&audio, &single_onset(), 44100, ExpressionMode::Cc11Only);
+
+        assert_eq!(result.len(), 1);
+        assert!(result[0].midi_velocity.is_none());
+        assert!(!result[0].cc11_envelope.is_empty());
+    }
+
+    #[test]
+    fn test_constant_level_note_produces_single_deduplicated_envelope_event() {
+        let audio = vec![0.5f32; 4410];
+        let result = analyze_note_expression(&audio, &single_onset(), 44100, ExpressionMode::Cc11Only);
+
+        assert_eq!(result[0].cc11_envelope.len(), 1, "a constant-amplitude note shouldn't repeat the same CC value");
+    }
+
+    #[test]
+    fn test_crescendo_produces_increasing_cc_values() {
+        // Linear ramp from silence to full scale over the note
+        let num_samples = 8820;
+        let audio: Vec<f32> = (0..num_samples)
+            .map(|i| i as f32 / num_samples as f32)
+            .collect();
+
+        let result = analyze_note_expression(&audio, &single_onset(), 44100, ExpressionMode::Cc11Only);
+        let envelope = &result[0].cc11_envelope;
+
+        assert!(envelope.len() > 1, "a crescendo should produce more than one CC11 event");
+        for window in envelope.windows(2) {
+            assert!(window[1].cc_value >= window[0].cc_value, "CC11 values should be non-decreasing on a crescendo");
+        }
+    }
+
+    #[test]
+    fn test_both_mode_emits_velocity_and_envelope() {
+        let audio = vec![0.5f32; 4410];
+        let result = analyze_note_expression(&audio, &single_onset(), 44100, ExpressionMode::Both);
+
+        assert!(result[0].midi_velocity.is_some());
+        assert!(!result[0].cc11_envelope.is_empty());
+    }
+}
+
+// ============================================================================
+// SILENCE-BASED SEGMENTATION
+// ============================================================================
+
+/// Analysis frame length used when scanning the RMS/dBFS envelope for silence
+const SILENCE_FRAME_MS: f64 = 10.0;
+
+/// A sample-index span `[start, end)`, used for both non-silent segments and silence gaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Scan the RMS/dBFS envelope of `samples` and split it into musically-active
+/// (non-silent) spans and the silence gaps between them.
+///
+/// A run of frames below `threshold_db` only becomes a silence gap once it
+/// lasts at least `min_silence_ms`; shorter dips stay part of the surrounding
+/// non-silent segment so brief rests inside a phrase don't fragment it.
+///
+/// # Returns
+/// `(non_silent_segments, silence_gaps)`, both sorted by start and covering
+/// the full length of `samples` between them with no overlap.
+pub fn split_silence_nonsilent(
+    samples: &[f32],
+    sample_rate: u32,
+    threshold_db: f32,
+    min_silence_ms: f64,
+) -> (Vec<Span>, Vec<Span>) {
+    if samples.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let frame_size = ((sample_rate as f64 * SILENCE_FRAME_MS / 1000.0) as usize).max(1);
+    let min_silence_frames = (min_silence_ms / SILENCE_FRAME_MS).ceil().max(1.0) as usize;
+
+    let num_frames = samples.len().div_ceil(frame_size);
+    let is_silent: Vec<bool> = (0..num_frames)
+        .map(|i| {
+            let start = i * frame_size;
+            let end = (start + frame_size).min(samples.len());
+            amplitude_to_db(calculate_rms(&samples[start..end])) < threshold_db
+        })
+        .collect();
+
+    // Merge runs of silent frames into candidate gaps, keeping only the ones
+    // that reach the minimum duration; everything else stays non-silent.
+    let mut silence_gaps = Vec::new();
+    let mut frame_idx = 0;
+    while frame_idx < num_frames {
+        if is_silent[frame_idx] {
+            let run_start = frame_idx;
+            while frame_idx < num_frames && is_silent[frame_idx] {
+                frame_idx += 1;
+            }
+            if frame_idx - run_start >= min_silence_frames {
+                let start = run_start * frame_size;
+                let end = (frame_idx * frame_size).min(samples.len());
+                silence_gaps.push(Span { start, end });
+            }
+        } else {
+            frame_idx += 1;
+        }
+    }
+
+    // Non-silent segments are whatever's left between (and around) the gaps
+    let mut non_silent_segments = Vec::new();
+    let mut cursor = 0;
+    for gap in &silence_gaps {
+        if gap.start > cursor {
+            non_silent_segments.push(Span { start: cursor, end: gap.start });
+        }
+        cursor = gap.end;
+    }
+    if cursor < samples.len() {
+        non_silent_segments.push(Span { start: cursor, end: samples.len() });
+    }
+
+    (non_silent_segments, silence_gaps)
+}
+
+/// Stitch processed non-silent segments back together with their original
+/// silence gaps preserved in order. The result has the same length as the
+/// source that `split_silence_nonsilent` was run on only when every processed
+/// segment kept its original length.
+///
+/// `processed` must have one entry per entry in `segments`, in the same order;
+/// each entry's length is whatever processing produced for that span (it need
+/// not match the original segment length). `original` supplies the untouched
+/// silence for each gap.
+pub fn recombine(original: &[f32], segments: &[Span], processed: &[Vec<f32>], gaps: &[Span]) -> Vec<f32> {
+    let mut pieces: Vec<(usize, &[f32])> = Vec::with_capacity(segments.len() + gaps.len());
+
+    for (segment, audio) in segments.iter().zip(processed.iter()) {
+        pieces.push((segment.start, audio.as_slice()));
+    }
+    for gap in gaps {
+        pieces.push((gap.start, &original[gap.start..gap.end]));
+    }
+
+    pieces.sort_by_key(|(start, _)| *start);
+    pieces.into_iter().flat_map(|(_, piece)| piece.iter().copied()).collect()
+}
+
+#[cfg(test)]
+mod silence_segmentation_tests {
+    use super::*;
+
+    #[test]
+    fn test_split_detects_one_gap() {
+        let sample_rate = 44100;
+        let mut samples = vec![0.5; sample_rate as usize / 2]; // 500ms loud
+        samples.extend(vec![0.0; sample_rate as usize]); // 1000ms silence
+        samples.extend(vec![0.5; sample_rate as usize / 2]); // 500ms loud
+
+        let (segments, gaps) = split_silence_nonsilent(&samples, sample_rate, -40.0, 200.0);
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(gaps.len(), 1);
+        assert!(gaps[0].end - gaps[0].start > 0);
+    }
+
+    #[test]
+    fn test_short_dip_does_not_split() {
+        let sample_rate = 44100;
+        let mut samples = vec![0.5; sample_rate as usize / 2];
+        samples.extend(vec![0.0; (sample_rate as f64 * 0.05) as usize]); // 50ms dip
+        samples.extend(vec![0.5; sample_rate as usize / 2]);
+
+        let (segments, gaps) = split_silence_nonsilent(&samples, sample_rate, -40.0, 200.0);
+
+        assert_eq!(segments.len(), 1, "A dip shorter than min_silence_ms should not split the segment");
+        assert!(gaps.is_empty());
+    }
+
+    #[test]
+    fn test_recombine_preserves_length() {
+        let sample_rate = 44100;
+        let mut samples = vec![0.5; sample_rate as usize / 2];
+        samples.extend(vec![0.0; sample_rate as usize]);
+        samples.extend(vec![0.5; sample_rate as usize / 2]);
+
+        let (segments, gaps) = split_silence_nonsilent(&samples, sample_rate, -40.0, 200.0);
+
+        // "Process" each segment as a no-op copy
+        let processed: Vec<Vec<f32>> = segments
+            .iter()
+            .map(|s| samples[s.start..s.end].to_vec())
+            .collect();
+
+        let recombined = recombine(&samples, &segments, &processed, &gaps);
+
+        assert_eq!(recombined.len(), samples.len());
+    }
+}
+
+// ============================================================================
+// KEY AND MODE DETECTION (chromagram + Krumhansl-Schmuckler)
+// ============================================================================
+
+/// Musical mode of a detected key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+/// Result of global key/mode detection
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct KeyResult {
+    /// Pitch class of the tonic, 0-11 (0 = C, 1 = C#/Db, ...)
+    pub tonic: u8,
+    pub mode: Mode,
+    /// Pearson correlation of the winning key profile against the chroma vector
+    pub confidence: f32,
+}
+
+const KEY_DETECTION_FFT_SIZE: usize = 4096;
+const KEY_DETECTION_HOP_SIZE: usize = 2048;
+const CHROMA_MAGNITUDE_FLOOR: f32 = 1e-6;
+const CHROMA_MIN_FREQ_HZ: f32 = 55.0;
+const CHROMA_MAX_FREQ_HZ: f32 = 5000.0;
+
+/// Krumhansl-Kessler major key profile, indexed by semitone above the tonic
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+/// Krumhansl-Kessler minor key profile, indexed by semitone above the tonic
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Estimate the global key and mode of a passage via a 12-bin chromagram
+/// correlated against the Krumhansl-Kessler tonal profiles.
+///
+/// Builds a chroma vector by mapping each FFT bin's center frequency to a
+/// pitch class and summing its magnitude across all STFT frames, then
+/// correlates that vector against all 24 circular rotations of the major and
+/// minor profiles (one per tonic), returning the best match.
+pub fn detect_key(samples: &[f32], sample_rate: u32) -> KeyResult {
+    let chroma = compute_chroma_vector(samples, sample_rate);
+
+    let mut best_tonic = 0u8;
+    let mut best_mode = Mode::Major;
+    let mut best_correlation = f32::NEG_INFINITY;
+
+    for tonic in 0..12usize {
+        let major_correlation = pearson_correlation(&chroma, &rotate_profile(&MAJOR_PROFILE, tonic));
+        if major_correlation > best_correlation {
+            best_correlation = major_correlation;
+            best_tonic = tonic as u8;
+            best_mode = Mode::Major;
+        }
+
+        let minor_correlation = pearson_correlation(&chroma, &rotate_profile(&MINOR_PROFILE, tonic));
+        if minor_correlation > best_correlation {
+            best_correlation = minor_correlation;
+            best_tonic = tonic as u8;
+            best_mode = Mode::Minor;
+        }
+    }
+
+    KeyResult {
+        tonic: best_tonic,
+        mode: best_mode,
+        confidence: best_correlation,
+    }
+}
+
+/// Build a 12-bin, L2-normalized chroma vector averaged across all STFT frames
+fn compute_chroma_vector(samples: &[f32], sample_rate: u32) -> [f32; 12] {
+    let frames = compute_stft(samples, KEY_DETECTION_FFT_SIZE, KEY_DETECTION_HOP_SIZE);
+    let mut chroma = [0.0f32; 12];
+
+    if frames.is_empty() {
+        return chroma;
+    }
+
+    for frame in &frames {
+        for (k, bin) in frame.iter().enumerate() {
+            let freq = k as f32 * sample_rate as f32 / KEY_DETECTION_FFT_SIZE as f32;
+            if freq < CHROMA_MIN_FREQ_HZ || freq > CHROMA_MAX_FREQ_HZ {
+                continue;
+            }
+
+            let magnitude = bin.norm();
+            if magnitude < CHROMA_MAGNITUDE_FLOOR {
+                continue;
+            }
+
+            let pitch_class = (12.0 * (freq / 440.0).log2() + 69.0).round() as i32;
+            let pitch_class = pitch_class.rem_euclid(12) as usize;
+            chroma[pitch_class] += magnitude;
+        }
+    }
+
+    let num_frames = frames.len() as f32;
+    for bin in chroma.iter_mut() {
+        *bin /= num_frames;
+    }
+
+    let norm = chroma.iter().map(|&v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for bin in chroma.iter_mut() {
+            *bin /= norm;
+        }
+    }
+
+    chroma
+}
+
+/// Rotate a 12-element key profile so index 0 lines up with `tonic`
+fn rotate_profile(profile: &[f32; 12], tonic: usize) -> [f32; 12] {
+    let mut rotated = [0.0f32; 12];
+    for (i, slot) in rotated.iter_mut().enumerate() {
+        *slot = profile[(i + 12 - tonic) % 12];
+    }
+    rotated
+}
+
+/// Pearson correlation coefficient between two equal-length vectors
+fn pearson_correlation(a: &[f32; 12], b: &[f32; 12]) -> f32 {
+    let mean_a = a.iter().sum::<f32>() / 12.0;
+    let mean_b = b.iter().sum::<f32>() / 12.0;
+
+    let mut covariance = 0.0f32;
+    let mut variance_a = 0.0f32;
+    let mut variance_b = 0.0f32;
+
+    for i in 0..12 {
+        let da = a[i] - mean_a;
+        let db = b[i] - mean_b;
+        covariance += da * db;
+        variance_a += da * da;
+        variance_b += db * db;
+    }
+
+    let denominator = (variance_a * variance_b).sqrt();
+    if denominator > 0.0 {
+        covariance / denominator
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod key_detection_tests {
+    use super::*;
+
+    fn sine_wave(freq: f32, sample_rate: u32, duration_secs: f32) -> Vec<f32> {
+        let num_samples = (sample_rate as f32 * duration_secs) as usize;
+        (0..num_samples)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_rotate_profile_identity() {
+        let rotated = rotate_profile(&MAJOR_PROFILE, 0);
+        assert_eq!(rotated, MAJOR_PROFILE);
+    }
+
+    #[test]
+    fn test_pearson_correlation_self_is_one() {
+        let correlation = pearson_correlation(&MAJOR_PROFILE, &MAJOR_PROFILE);
+        assert!((correlation - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_detect_key_c_major_tonic() {
+        let sample_rate = 44100;
+        // A simple C major triad (C4, E4, G4) sustained, favors a C major chroma
+        let mut samples = sine_wave(261.63, sample_rate, 2.0);
+        for (i, s) in sine_wave(329.63, sample_rate, 2.0).into_iter().enumerate() {
+            samples[i] += s;
+        }
+        for (i, s) in sine_wave(392.00, sample_rate, 2.0).into_iter().enumerate() {
+            samples[i] += s;
+        }
+
+        let result = detect_key(&samples, sample_rate);
+
+        assert_eq!(result.tonic, 0, "Expected tonic pitch class C (0)");
+        assert_eq!(result.mode, Mode::Major);
+    }
+
+    #[test]
+    fn test_detect_key_empty_samples_does_not_panic() {
+        let result = detect_key(&[], 44100);
+        assert_eq!(result.confidence, 0.0);
+    }
+}
+
+// ============================================================================
+// CHORD DETECTION (Harmonic Product Spectrum)
+// ============================================================================
+
+/// Parameters for polyphonic chord detection via Harmonic Product Spectrum
+#[derive(Debug, Clone)]
+pub struct ChordParams {
+    pub fft_size: usize,
+    pub sample_rate: u32,
+    /// Maximum number of simultaneous notes to return
+    pub max_notes: usize,
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+    /// Number of harmonic downsamples multiplied together per HPS bin (2..=this)
+    pub harmonics: usize,
+}
+
+impl Default for ChordParams {
+    fn default() -> Self {
+        Self {
+            fft_size: 4096,
+            sample_rate: 44100,
+            max_notes: 6,
+            min_frequency: 27.5,   // A0
+            max_frequency: 4186.0, // C8
+            harmonics: 5,
+        }
+    }
+}
+
+/// Detect up to `params.max_notes` simultaneous fundamental frequencies via a
+/// Harmonic Product Spectrum (HPS).
+///
+/// YIN assumes a single fundamental, which breaks down on piano chords; HPS
+/// pointwise-multiplies the magnitude spectrum with integer-downsampled
+/// copies of itself (factors 2..=`harmonics`), which suppresses harmonics and
+/// reinforces true fundamentals, tolerating several simultaneous notes.
+///
+/// # Arguments:
+/// * `samples` - Audio samples (mono, f32, normalized to ±1.0); only the
+///   first `params.fft_size` samples are analyzed
+/// * `params` - HPS parameters
+///
+/// # Returns:
+/// * Up to `params.max_notes` detected notes, strongest first
+pub fn detect_chord(samples: &[f32], params: &ChordParams) -> Vec<PitchResult> {
+    if samples.len() < params.fft_size {
+        return Vec::new();
+    }
+
+    let rms = calculate_rms(samples);
+    if rms < 0.01 {
+        return Vec::new();
+    }
+
+    let window = hann_window(params.fft_size);
+    let mut windowed: Vec<f32> = samples[..params.fft_size]
+        .iter()
+        .zip(window.iter())
+        .map(|(&s, &w)| s * w)
+        .collect();
+
+    let mut planner = RealFftPlanner::<f32>::new();
+    let r2c = planner.plan_fft_forward(params.fft_size);
+    let mut spectrum = r2c.make_output_vec();
+    r2c.process(&mut windowed, &mut spectrum).unwrap();
+
+    let magnitude: Vec<f32> = spectrum.iter().map(|c| c.norm()).collect();
+
+    // hps[k] = product over r=1..=harmonics of mag[k*r], bounded by spectrum length
+    let mut hps = magnitude.clone();
+    for r in 2..=params.harmonics.max(2) {
+        for (k, hps_bin) in hps.iter_mut().enumerate() {
+            let downsampled_index = k * r;
+            if downsampled_index < magnitude.len() {
+                *hps_bin *= magnitude[downsampled_index];
+            } else {
+                *hps_bin = 0.0;
+            }
+        }
+    }
+
+    let bin_hz = params.sample_rate as f32 / params.fft_size as f32;
+    let min_bin = ((params.min_frequency / bin_hz).floor() as usize).max(1);
+    let max_bin = ((params.max_frequency / bin_hz).ceil() as usize).min(hps.len().saturating_sub(2));
+
+    // Minimum bin separation between peaks, corresponding to one semitone at
+    // the lowest frequency in range, so adjacent bins of the same note don't
+    // get picked as distinct notes
+    let min_peak_separation = ((params.min_frequency * (2.0f32.powf(1.0 / 12.0) - 1.0)) / bin_hz)
+        .round()
+        .max(1.0) as usize;
+
+    let spectral_mean: f32 = if !magnitude.is_empty() {
+        magnitude.iter().sum::<f32>() / magnitude.len() as f32
+    } else {
+        0.0
+    };
+
+    // Peak-pick local maxima in the HPS within range, strongest first
+    let mut peaks: Vec<(usize, f32)> = Vec::new();
+    for k in min_bin..max_bin {
+        if hps[k] > 0.0 && hps[k] > hps[k - 1] && hps[k] >= hps[k + 1] {
+            peaks.push((k, hps[k]));
+        }
+    }
+    peaks.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    let mut selected: Vec<usize> = Vec::new();
+    for (bin, _) in peaks {
+        let far_enough = selected
+            .iter()
+            .all(|&s| bin.abs_diff(s) >= min_peak_separation);
+        if far_enough {
+            selected.push(bin);
+        }
+        if selected.len() >= params.max_notes {
+            break;
+        }
+    }
+
+    selected
+        .into_iter()
+        .map(|bin| {
+            let frequency = parabolic_peak_frequency(&magnitude, bin, bin_hz);
+            let midi_note = frequency_to_midi(frequency);
+            let exact_midi = 69.0 + 12.0 * (frequency / 440.0).log2();
+            let cents_offset = (exact_midi - midi_note as f32) * 100.0;
+            let confidence = if spectral_mean > 0.0 {
+                (magnitude[bin] / (spectral_mean * 4.0)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+
+            PitchResult {
+                frequency,
+                confidence,
+                midi_note,
+                cents_offset,
+                rms_level: rms,
+            }
+        })
+        .collect()
+}
+
+/// Parabolic interpolation of the true peak frequency around magnitude spectrum bin `bin`
+fn parabolic_peak_frequency(magnitude: &[f32], bin: usize, bin_hz: f32) -> f32 {
+    if bin == 0 || bin + 1 >= magnitude.len() {
+        return bin as f32 * bin_hz;
+    }
+
+    let s0 = magnitude[bin - 1];
+    let s1 = magnitude[bin];
+    let s2 = magnitude[bin + 1];
+    let denom = s0 - 2.0 * s1 + s2;
+    let offset = if denom.abs() > 1e-9 { 0.5 * (s0 - s2) / denom } else { 0.0 };
+
+    (bin as f32 + offset) * bin_hz
+}
+
+#[cfg(test)]
+mod chord_detection_tests {
+    use super::*;
+
+    fn generate_sine_wave(frequency: f32, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                (2.0 * std::f32::consts::PI * frequency * t).sin()
+            })
+            .collect()
+    }
+
+    /// First five harmonics at 1/h amplitude, a rough stand-in for a piano
+    /// tone: HPS only reinforces a fundamental whose harmonics are present
+    fn generate_harmonic_tone(frequency: f32, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        let mut samples = vec![0.0f32; num_samples];
+        for h in 1..=5 {
+            for (i, s) in generate_sine_wave(frequency * h as f32, sample_rate, num_samples).into_iter().enumerate() {
+                samples[i] += s / h as f32;
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_detect_chord_single_note() {
+        let sample_rate = 44100;
+        let samples = generate_harmonic_tone(440.0, sample_rate, 8192);
+        let params = ChordParams { sample_rate, fft_size: 8192, ..Default::default() };
+
+        let notes = detect_chord(&samples, &params);
+
+        assert!(!notes.is_empty());
+        assert_eq!(notes[0].midi_note, 69, "Strongest note should be A4 (69)");
+    }
+
+    #[test]
+    fn test_detect_chord_two_notes() {
+        let sample_rate = 44100;
+        let mut samples = generate_harmonic_tone(261.63, sample_rate, 8192); // C4
+        for (i, s) in generate_harmonic_tone(392.00, sample_rate, 8192).into_iter().enumerate() {
+            samples[i] += s; // G4
+        }
+        let params = ChordParams { sample_rate, fft_size: 8192, max_notes: 2, ..Default::default() };
+
+        let notes = detect_chord(&samples, &params);
+
+        let midi_notes: Vec<u8> = notes.iter().map(|n| n.midi_note).collect();
+        assert!(midi_notes.contains(&60) || midi_notes.contains(&67), "Expected C4 or G4 among detected notes: {:?}", midi_notes);
+    }
+
+    #[test]
+    fn test_detect_chord_silence_returns_empty() {
+        let params = ChordParams::default();
+        let samples = vec![0.0f32; params.fft_size];
+
+        let notes = detect_chord(&samples, &params);
+
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_detect_chord_respects_max_notes() {
+        let sample_rate = 44100;
+        let mut samples = vec![0.0f32; 8192];
+        for freq in [261.63, 329.63, 392.00, 493.88, 587.33, 698.46] {
+            for (i, s) in generate_sine_wave(freq, sample_rate, 8192).into_iter().enumerate() {
+                samples[i] += s;
+            }
+        }
+        let params = ChordParams { sample_rate, fft_size: 8192, max_notes: 3, ..Default::default() };
+
+        let notes = detect_chord(&samples, &params);
+
+        assert!(notes.len() <= 3);
+    }
+}
+
+// ============================================================================
+// STREAMING ANALYSIS (persistent state for real-time frame-by-frame use)
+// ============================================================================
+
+/// One frame of results emitted by `StreamingAnalyzer::push`
+#[derive(Debug, Clone)]
+pub struct AnalysisFrame {
+    /// Pitch from the most recent hop of this push in which one was detected, if any
+    pub pitch: Option<PitchResult>,
+    /// Onsets that crossed the detection threshold during this push, in order
+    pub onsets: Vec<OnsetEvent>,
+    /// Dynamics for the most recent hop processed by this push
+    pub dynamics: Option<DynamicsEvent>,
+}
+
+/// Real-time analyzer holding state across calls, so onset timestamps,
+/// inter-onset gating, and spectral-flux continuity survive block
+/// boundaries instead of resetting the way the whole-buffer functions above
+/// do when called repeatedly on a live audio callback.
+///
+/// Unlike `detect_onsets`, onset picking here can't look ahead across a hop
+/// boundary, so it uses a simpler threshold-crossing test in place of the
+/// three-point local-maximum search; confidence is the raw flux value capped
+/// at 1.0 instead of a ratio against a local window.
+pub struct StreamingAnalyzer {
+    sample_rate: u32,
+    fft_size: usize,
+    hop_size: usize,
+    onset_params: OnsetParams,
+    yin_params: YinParams,
+
+    ring_buffer: VecDeque<f32>,
+    prev_magnitude: Option<Vec<f32>>,
+    samples_consumed: u64,
+    last_onset_timestamp: Option<f64>,
+    r2c: Arc<dyn realfft::RealToComplex<f32>>,
+}
+
+impl StreamingAnalyzer {
+    /// Create a streaming analyzer with default onset/pitch parameters for `sample_rate`
+    pub fn new(sample_rate: u32) -> Self {
+        Self::with_params(
+            OnsetParams { sample_rate, ..Default::default() },
+            YinParams { sample_rate, ..Default::default() },
+        )
+    }
+
+    /// Create a streaming analyzer with custom onset/pitch parameters. The
+    /// STFT window and hop come from `onset_params.fft_size`/`hop_size`.
+    pub fn with_params(onset_params: OnsetParams, yin_params: YinParams) -> Self {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(onset_params.fft_size);
+
+        Self {
+            sample_rate: onset_params.sample_rate,
+            fft_size: onset_params.fft_size,
+            hop_size: onset_params.hop_size,
+            onset_params,
+            yin_params,
+            ring_buffer: VecDeque::new(),
+            prev_magnitude: None,
+            samples_consumed: 0,
+            last_onset_timestamp: None,
+            r2c,
+        }
+    }
+
+    /// Feed an arbitrarily-sized block of new audio samples, advancing the
+    /// analyzer by as many `hop_size`-sized frames as are now available and
+    /// returning the events produced, with globally-correct absolute
+    /// timestamps that keep incrementing across calls.
+    pub fn push(&mut self, block: &[f32]) -> AnalysisFrame {
+        self.ring_buffer.extend(block.iter().copied());
+
+        let mut onsets = Vec::new();
+        let mut latest_pitch = None;
+        let mut latest_dynamics = None;
+
+        while self.ring_buffer.len() >= self.fft_size {
+            let window: Vec<f32> = self.ring_buffer.iter().take(self.fft_size).copied().collect();
+
+            if let Some(onset) = self.process_onset_frame(&window) {
+                onsets.push(onset);
+            }
+
+            // Cheap enough to recompute every hop; later hops simply overwrite earlier ones
+            if let Some(pitch) = detect_pitch_yin(&window, &self.yin_params) {
+                latest_pitch = Some(pitch);
+            }
+            latest_dynamics = Some(self.dynamics_for_frame(&window));
+
+            for _ in 0..self.hop_size {
+                self.ring_buffer.pop_front();
+            }
+            self.samples_consumed += self.hop_size as u64;
+        }
+
+        AnalysisFrame { pitch: latest_pitch, onsets, dynamics: latest_dynamics }
+    }
+
+    fn process_onset_frame(&mut self, window: &[f32]) -> Option<OnsetEvent> {
+        let windowed_samples: Vec<f32> = window
+            .iter()
+            .zip(hann_window(self.fft_size).iter())
+            .map(|(&s, &w)| s * w)
+            .collect();
+
+        let mut spectrum = self.r2c.make_output_vec();
+        let mut input = windowed_samples;
+        self.r2c.process(&mut input, &mut spectrum).unwrap();
+        let magnitude: Vec<f32> = spectrum.iter().map(|c| c.norm()).collect();
+
+        let flux = match &self.prev_magnitude {
+            Some(prev) => magnitude
+                .iter()
+                .zip(prev.iter())
+                .map(|(&m, &p)| (m - p).max(0.0))
+                .sum::<f32>(),
+            None => 0.0,
+        };
+        self.prev_magnitude = Some(magnitude);
+
+        let energy = calculate_rms(window);
+        let hop_time = self.hop_size as f64 / self.sample_rate as f64;
+        let timestamp = (self.samples_consumed as f64 / self.sample_rate as f64) + hop_time;
+        let sample_index = self.samples_consumed as usize + self.hop_size;
+
+        if flux <= self.onset_params.threshold || energy <= self.onset_params.energy_threshold {
+            return None;
+        }
+
+        let far_enough_from_last = match self.last_onset_timestamp {
+            Some(last) => timestamp - last >= self.onset_params.min_inter_onset,
+            None => true,
+        };
+        if !far_enough_from_last {
+            return None;
+        }
+
+        self.last_onset_timestamp = Some(timestamp);
+        Some(OnsetEvent {
+            timestamp,
+            sample_index,
+            strength: flux,
+            confidence: flux.min(1.0),
+        })
+    }
+
+    fn dynamics_for_frame(&self, window: &[f32]) -> DynamicsEvent {
+        let rms = calculate_rms(window);
+        let peak = find_peak(window);
+        let db = amplitude_to_db(rms);
+
+        DynamicsEvent {
+            timestamp: self.samples_consumed as f64 / self.sample_rate as f64,
+            rms_level: rms,
+            peak_level: peak,
+            db_level: db,
+            midi_velocity: db_to_velocity(db),
+        }
+    }
+}
+
+#[cfg(test)]
+mod streaming_analyzer_tests {
+    use super::*;
+
+    fn generate_sine_wave(frequency: f32, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                (2.0 * std::f32::consts::PI * frequency * t).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_push_small_block_produces_no_frame_yet() {
+        let mut analyzer = StreamingAnalyzer::new(44100);
+        let frame = analyzer.push(&[0.0; 10]);
+
+        assert!(frame.pitch.is_none());
+        assert!(frame.dynamics.is_none());
+        assert!(frame.onsets.is_empty());
+    }
+
+    #[test]
+    fn test_push_full_window_produces_dynamics() {
+        let mut analyzer = StreamingAnalyzer::new(44100);
+        let samples = generate_sine_wave(440.0, 44100, 512);
+
+        let frame = analyzer.push(&samples);
+
+        assert!(frame.dynamics.is_some());
+    }
+
+    #[test]
+    fn test_timestamps_advance_across_pushes() {
+        let mut analyzer = StreamingAnalyzer::new(44100);
+        let samples = generate_sine_wave(440.0, 44100, 512);
+
+        let first = analyzer.push(&samples).dynamics.unwrap();
+        let second = analyzer.push(&samples).dynamics.unwrap();
+
+        assert!(second.timestamp > first.timestamp);
+    }
+
+    #[test]
+    fn test_min_inter_onset_gating_survives_across_pushes() {
+        let mut params = OnsetParams::default();
+        params.min_inter_onset = 10.0; // effectively "never again" within this test
+        let mut analyzer = StreamingAnalyzer::with_params(params, YinParams::default());
+
+        // Silence, then a loud transient split across two pushes
+        let silence = vec![0.0f32; 512];
+        let loud = generate_sine_wave(440.0, 44100, 512);
+
+        let first = analyzer.push(&silence);
+        let middle = analyzer.push(&loud);
+        let second = analyzer.push(&loud);
+
+        // Whatever onset(s) fired, a second push within min_inter_onset of the
+        // first must not also report one
+        let total_onsets = first.onsets.len() + middle.onsets.len() + second.onsets.len();
+        assert!(total_onsets <= 1);
+    }
+}
+
+// ============================================================================
+// TEMPO / BPM ESTIMATION
+// ============================================================================
+
+/// Parameters for tempo estimation
+#[derive(Debug, Clone, Copy)]
+pub struct TempoParams {
+    pub min_bpm: f32,
+    pub max_bpm: f32,
+    /// Histogram bin width in BPM
+    pub bin_width_bpm: f32,
+}
+
+impl Default for TempoParams {
+    fn default() -> Self {
+        Self {
+            min_bpm: 40.0,
+            max_bpm: 240.0,
+            bin_width_bpm: 1.0,
+        }
+    }
+}
+
+/// Result of tempo estimation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TempoResult {
+    pub bpm: f32,
+    /// Prominence of the winning BPM bin over the mean of the folded histogram (0.0-1.0)
+    pub confidence: f32,
+    /// Seconds from t=0 to the first inferred beat, for aligning a metronome grid
+    pub beat_phase: f64,
+}
+
+/// Estimate tempo (BPM) from a sequence of onset events
+///
+/// Builds a histogram of inter-onset intervals (every pair, not just
+/// consecutive onsets, so sparse or syncopated playing still contributes)
+/// converted to BPM, then folds half/double-tempo candidates into their
+/// fundamental bin before picking the peak — a strong candidate at 2x or
+/// 0.5x the true tempo usually just reflects the same underlying beat.
+///
+/// # Returns
+/// `None` if fewer than two onsets are given or no interval falls in range
+pub fn estimate_tempo(onsets: &[OnsetEvent], params: &TempoParams) -> Option<TempoResult> {
+    if onsets.len() < 2 {
+        return None;
+    }
+
+    let min_period = 60.0 / params.max_bpm as f64;
+    let max_period = 60.0 / params.min_bpm as f64;
+
+    // All pairwise IOIs within the tempo search range; onsets are
+    // timestamp-sorted so once an interval exceeds max_period, later j's
+    // for the same i will only be larger
+    let mut intervals = Vec::new();
+    for i in 0..onsets.len() {
+        for j in (i + 1)..onsets.len() {
+            let ioi = onsets[j].timestamp - onsets[i].timestamp;
+            if ioi > max_period {
+                break;
+            }
+            if ioi >= min_period {
+                intervals.push(ioi);
+            }
+        }
+    }
+
+    if intervals.is_empty() {
+        return None;
+    }
+
+    let num_bins = ((params.max_bpm - params.min_bpm) / params.bin_width_bpm).ceil() as usize + 1;
+    let mut histogram = vec![0.0f32; num_bins];
+
+    for &ioi in &intervals {
+        let bpm = (60.0 / ioi) as f32;
+        if bpm < params.min_bpm || bpm > params.max_bpm {
+            continue;
+        }
+        let bin = ((bpm - params.min_bpm) / params.bin_width_bpm).round() as usize;
+        if let Some(slot) = histogram.get_mut(bin) {
+            *slot += 1.0;
+        }
+    }
+
+    // Fold octave-related candidates: half- and double-tempo support counts
+    // toward the fundamental bin, weighted down since it's secondary evidence
+    let mut folded = histogram.clone();
+    for (bin, slot) in folded.iter_mut().enumerate() {
+        let bpm = params.min_bpm + bin as f32 * params.bin_width_bpm;
+
+        let half_bpm = bpm / 2.0;
+        if half_bpm >= params.min_bpm {
+            let half_bin = ((half_bpm - params.min_bpm) / params.bin_width_bpm).round() as usize;
+            if let Some(&support) = histogram.get(half_bin) {
+                *slot += support * 0.5;
+            }
+        }
+
+        let double_bpm = bpm * 2.0;
+        if double_bpm <= params.max_bpm {
+            let double_bin = ((double_bpm - params.min_bpm) / params.bin_width_bpm).round() as usize;
+            if let Some(&support) = histogram.get(double_bin) {
+                *slot += support * 0.5;
+            }
+        }
+    }
+
+    let (best_bin, &best_value) = folded
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())?;
+    if best_value <= 0.0 {
+        return None;
+    }
+
+    let bpm = params.min_bpm + best_bin as f32 * params.bin_width_bpm;
+    let mean = folded.iter().sum::<f32>() / folded.len() as f32;
+    let confidence = ((best_value - mean) / best_value).clamp(0.0, 1.0);
+
+    // Offset of the first onset modulo the beat period, so a metronome grid
+    // starting at t=0 can be aligned to the detected onsets
+    let period = 60.0 / bpm as f64;
+    let beat_phase = onsets[0].timestamp % period;
+
+    Some(TempoResult { bpm, confidence, beat_phase })
+}
+
+#[cfg(test)]
+mod tempo_tests {
+    use super::*;
+
+    fn onsets_at_bpm(bpm: f32, count: usize) -> Vec<OnsetEvent> {
+        let period = 60.0 / bpm as f64;
+        (0..count)
+            .map(|i| OnsetEvent {
+                timestamp: i as f64 * period,
+                sample_index: 0,
+                strength: 1.0,
+                confidence: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_estimate_tempo_steady_120_bpm() {
+        let onsets = onsets_at_bpm(120.0, 16);
+        let result = estimate_tempo(&onsets, &TempoParams::default()).unwrap();
+
+        assert!((result.bpm - 120.0).abs() < 2.0, "Expected ~120 BPM, got {}", result.bpm);
+        assert!(result.confidence > 0.0);
+    }
+
+    #[test]
+    fn test_estimate_tempo_too_few_onsets_returns_none() {
+        let onsets = onsets_at_bpm(120.0, 1);
+        assert!(estimate_tempo(&onsets, &TempoParams::default()).is_none());
+    }
+
+    #[test]
+    fn test_estimate_tempo_beat_phase_is_within_one_period() {
+        let onsets = onsets_at_bpm(100.0, 8);
+        let result = estimate_tempo(&onsets, &TempoParams::default()).unwrap();
+
+        let period = 60.0 / result.bpm as f64;
+        assert!(result.beat_phase >= 0.0 && result.beat_phase < period);
+    }
+}
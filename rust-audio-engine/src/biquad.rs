@@ -0,0 +1,236 @@
+//! Biquad filters (RBJ Audio EQ Cookbook, Direct Form I)
+//!
+//! Minimal highpass/lowpass/bandpass biquads, plus an A-weighting cascade
+//! built from them, used to band-limit or perceptually weight audio before
+//! pitch/onset/dynamics analysis.
+
+use std::f32::consts::PI;
+
+/// A single second-order IIR section in Direct Form I, with its own
+/// `x1,x2,y1,y2` delay-line state so repeated `process` calls on streaming
+/// blocks stay continuous across calls.
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    fn from_coefficients(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    /// RBJ-cookbook highpass biquad
+    pub fn highpass(sample_rate: f32, cutoff_hz: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * cutoff_hz / sample_rate;
+        let cos_w0 = w0.cos();
+        let alpha = w0.sin() / (2.0 * q);
+
+        let b0 = (1.0 + cos_w0) / 2.0;
+        let b1 = -(1.0 + cos_w0);
+        let b2 = (1.0 + cos_w0) / 2.0;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_w0;
+        let a2 = 1.0 - alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// RBJ-cookbook lowpass biquad
+    pub fn lowpass(sample_rate: f32, cutoff_hz: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * cutoff_hz / sample_rate;
+        let cos_w0 = w0.cos();
+        let alpha = w0.sin() / (2.0 * q);
+
+        let b0 = (1.0 - cos_w0) / 2.0;
+        let b1 = 1.0 - cos_w0;
+        let b2 = (1.0 - cos_w0) / 2.0;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_w0;
+        let a2 = 1.0 - alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// RBJ-cookbook constant-skirt-gain bandpass biquad, centered at `center_hz`
+    pub fn bandpass(sample_rate: f32, center_hz: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * center_hz / sample_rate;
+        let cos_w0 = w0.cos();
+        let sin_w0 = w0.sin();
+        let alpha = sin_w0 / (2.0 * q);
+
+        let b0 = sin_w0 / 2.0;
+        let b1 = 0.0;
+        let b2 = -sin_w0 / 2.0;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_w0;
+        let a2 = 1.0 - alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// One second-order section with two real poles (rad/s, negative) and no
+    /// zeros, normalized to unity gain at DC, discretized via the bilinear
+    /// transform. Used to build the A-weighting cascade from its analog
+    /// pole/zero prototype.
+    fn from_real_pole_pair(sample_rate: f32, p1: f32, p2: f32) -> Self {
+        Self::bilinear_section(sample_rate, 0.0, 0.0, p1 * p2, -(p1 + p2), p1 * p2)
+    }
+
+    /// One second-order section with a double zero at the origin (`s^2` in
+    /// the numerator) and two real poles, discretized via the bilinear transform
+    fn from_real_pole_pair_with_double_zero(sample_rate: f32, p1: f32, p2: f32) -> Self {
+        Self::bilinear_section(sample_rate, 1.0, 0.0, 0.0, -(p1 + p2), p1 * p2)
+    }
+
+    /// Bilinear-transform an analog second-order section
+    /// `(c0*s^2 + c1*s + c2) / (s^2 + d1*s + d2)` into a digital biquad, via
+    /// the substitution `s = 2*fs*(1 - z^-1)/(1 + z^-1)`.
+    fn bilinear_section(sample_rate: f32, c0: f32, c1: f32, c2: f32, d1: f32, d2: f32) -> Self {
+        let k = 2.0 * sample_rate;
+        let k2 = k * k;
+
+        let b0 = c0 * k2 + c1 * k + c2;
+        let b1 = -2.0 * c0 * k2 + 2.0 * c2;
+        let b2 = c0 * k2 - c1 * k + c2;
+
+        let a0 = k2 + d1 * k + d2;
+        let a1 = -2.0 * k2 + 2.0 * d2;
+        let a2 = k2 - d1 * k + d2;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// Process a block of samples in place, Direct Form I, preserving state across calls
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let x0 = *sample;
+            let y0 = self.b0 * x0 + self.b1 * self.x1 + self.b2 * self.x2
+                - self.a1 * self.y1
+                - self.a2 * self.y2;
+
+            self.x2 = self.x1;
+            self.x1 = x0;
+            self.y2 = self.y1;
+            self.y1 = y0;
+
+            *sample = y0;
+        }
+    }
+}
+
+/// Cascade of biquad sections applied in series, each keeping its own state
+pub struct BiquadCascade {
+    stages: Vec<Biquad>,
+}
+
+impl BiquadCascade {
+    pub fn new(stages: Vec<Biquad>) -> Self {
+        Self { stages }
+    }
+
+    /// Approximate ANSI S1.4 A-weighting filter, built as three cascaded
+    /// bilinear-transformed sections from the standard analog pole/zero
+    /// prototype (four zeros at the origin; poles at ~20.6 Hz (double),
+    /// ~107.7 Hz, ~737.9 Hz, and ~12194.2 Hz (double)).
+    ///
+    /// Not calibrated to exactly 0 dB at 1 kHz — it's used here to weight
+    /// relative loudness for dynamics analysis, not as a certified meter.
+    pub fn a_weighting(sample_rate: f32) -> Self {
+        let two_pi = 2.0 * PI;
+        let p1 = -two_pi * 20.598997;
+        let p2 = -two_pi * 107.65265;
+        let p3 = -two_pi * 737.86223;
+        let p4 = -two_pi * 12194.217;
+
+        Self::new(vec![
+            Biquad::from_real_pole_pair_with_double_zero(sample_rate, p1, p1),
+            Biquad::from_real_pole_pair_with_double_zero(sample_rate, p2, p3),
+            Biquad::from_real_pole_pair(sample_rate, p4, p4),
+        ])
+    }
+
+    /// Process a block of samples in place, running every stage in series
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for stage in self.stages.iter_mut() {
+            stage.process(samples);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine_wave(freq: f32, sample_rate: f32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| (2.0 * PI * freq * i as f32 / sample_rate).sin())
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|&s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_highpass_attenuates_low_frequency() {
+        let sample_rate = 44100.0;
+        let mut low = sine_wave(20.0, sample_rate, 8192);
+        let original_rms = rms(&low);
+
+        Biquad::highpass(sample_rate, 40.0, 0.707).process(&mut low);
+
+        assert!(rms(&low) < original_rms * 0.5, "20 Hz tone should be strongly attenuated by a 40 Hz highpass");
+    }
+
+    #[test]
+    fn test_highpass_passes_high_frequency() {
+        let sample_rate = 44100.0;
+        let mut high = sine_wave(2000.0, sample_rate, 8192);
+        let original_rms = rms(&high);
+
+        Biquad::highpass(sample_rate, 40.0, 0.707).process(&mut high);
+
+        assert!(rms(&high) > original_rms * 0.9, "2 kHz tone should pass a 40 Hz highpass mostly unattenuated");
+    }
+
+    #[test]
+    fn test_lowpass_attenuates_high_frequency() {
+        let sample_rate = 44100.0;
+        let mut high = sine_wave(15000.0, sample_rate, 8192);
+        let original_rms = rms(&high);
+
+        Biquad::lowpass(sample_rate, 1000.0, 0.707).process(&mut high);
+
+        assert!(rms(&high) < original_rms * 0.5);
+    }
+
+    #[test]
+    fn test_a_weighting_attenuates_sub_bass_more_than_midrange() {
+        let sample_rate = 44100.0;
+        let mut low = sine_wave(31.5, sample_rate, 8192);
+        let mut mid = sine_wave(1000.0, sample_rate, 8192);
+
+        BiquadCascade::a_weighting(sample_rate).process(&mut low);
+        BiquadCascade::a_weighting(sample_rate).process(&mut mid);
+
+        assert!(rms(&low) < rms(&mid), "A-weighting should attenuate 31.5 Hz far more than 1 kHz");
+    }
+}
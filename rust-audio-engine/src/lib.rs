@@ -8,17 +8,24 @@
 use pyo3::prelude::*;
 use pyo3::exceptions::PyRuntimeError;
 use std::path::Path;
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 mod synthesizer;
 mod metal_effects;
 mod waveform;
 mod analyzer;
+mod live;
+mod recording;
+mod output;
+mod biquad;
 
-use synthesizer::MidiSynthesizer;
+use synthesizer::{MidiSynthesizer, ExpressionParams};
 use metal_effects::MetalEffectsProcessor;
-use waveform::WaveformGenerator;
-use analyzer::{detect_pitch_yin, YinParams, PitchResult, detect_onsets, OnsetParams, OnsetEvent};
+use waveform::{WaveformGenerator, WaveformConfig, SamplingMethod, ChannelMode, Range, AudioData, BinnedWaveformRenderer};
+use analyzer::{YinParams, PitchResult, detect_onsets, OnsetParams, OnsetEvent, Mode};
+use live::LiveSessionHandle;
+use metal_effects::GranularParams;
+use output::Normalization;
 
 /// Synthesize a MIDI file to WAV audio with optional GPU effects
 ///
@@ -29,11 +36,29 @@ use analyzer::{detect_pitch_yin, YinParams, PitchResult, detect_onsets, OnsetPar
 ///     sample_rate: Sample rate in Hz (default: 44100)
 ///     use_gpu: Enable Metal GPU effects (default: true)
 ///     reverb: Enable reverb effect (default: true)
+///     reverb_ir_path: Optional impulse-response WAV for convolution reverb
+///         (falls back to the synthetic Freeverb decay when omitted)
+///     granular: Enable the granular sustain/pad effect (default: false)
+///     granular_grain_ms: Grain length in milliseconds (default: 40.0)
+///     granular_density_hz: Grains triggered per second (default: 20.0)
+///     granular_overlap: Fraction of each grain overlapping the next, 0.0-0.95 (default: 0.5)
+///     granular_pitch_ratio: Playback-rate offset for each grain, 1.0 = original pitch (default: 1.0)
+///     granular_mix: Wet/dry mix for the granular effect, 0.0-1.0 (default: 0.5)
+///     tuning_cents: Global fine-tune offset in cents, -100.0 to 100.0 (default: 0.0)
+///     channel_volumes: Per-channel volume multipliers, 1.0 = unity (default: none, all unity)
+///     release: Release/falloff amount, 0.0-1.0, 0.5 leaves the SoundFont's envelope
+///         unchanged (default: 0.5)
 ///
 /// Returns:
 ///     Duration in seconds of generated audio
 #[pyfunction]
-#[pyo3(signature = (midi_path, output_path, soundfont_path, sample_rate=44100, use_gpu=true, reverb=true))]
+#[pyo3(signature = (
+    midi_path, output_path, soundfont_path, sample_rate=44100, use_gpu=true, reverb=true,
+    reverb_ir_path=None, granular=false, granular_grain_ms=40.0, granular_density_hz=20.0,
+    granular_overlap=0.5, granular_pitch_ratio=1.0, granular_mix=0.5,
+    tuning_cents=0.0, channel_volumes=None, release=0.5
+))]
+#[allow(clippy::too_many_arguments)]
 fn synthesize_midi(
     midi_path: String,
     output_path: String,
@@ -41,7 +66,30 @@ fn synthesize_midi(
     sample_rate: u32,
     use_gpu: bool,
     reverb: bool,
+    reverb_ir_path: Option<String>,
+    granular: bool,
+    granular_grain_ms: f32,
+    granular_density_hz: f32,
+    granular_overlap: f32,
+    granular_pitch_ratio: f32,
+    granular_mix: f32,
+    tuning_cents: f32,
+    channel_volumes: Option<Vec<f32>>,
+    release: f32,
 ) -> PyResult<f64> {
+    let granular_params = GranularParams {
+        grain_size_ms: granular_grain_ms,
+        density_hz: granular_density_hz,
+        overlap: granular_overlap,
+        pitch_ratio: granular_pitch_ratio,
+        mix: granular_mix,
+    };
+    let expression_params = ExpressionParams {
+        tuning_cents,
+        channel_volumes: channel_volumes.unwrap_or_default(),
+        release,
+    };
+
     synthesize_midi_internal(
         &midi_path,
         &output_path,
@@ -49,10 +97,15 @@ fn synthesize_midi(
         sample_rate,
         use_gpu,
         reverb,
+        reverb_ir_path.as_deref(),
+        granular,
+        &granular_params,
+        &expression_params,
     )
     .map_err(|e| PyRuntimeError::new_err(format!("Synthesis failed: {}", e)))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn synthesize_midi_internal(
     midi_path: &str,
     output_path: &str,
@@ -60,15 +113,19 @@ fn synthesize_midi_internal(
     sample_rate: u32,
     use_gpu: bool,
     reverb: bool,
+    reverb_ir_path: Option<&str>,
+    granular: bool,
+    granular_params: &GranularParams,
+    expression_params: &ExpressionParams,
 ) -> Result<f64> {
     // 1. Synthesize MIDI using rustysynth (CPU)
     let mut synthesizer = MidiSynthesizer::new(soundfont_path, sample_rate)?;
-    let audio_samples = synthesizer.synthesize_file(midi_path)?;
+    let audio_samples = synthesizer.synthesize_file_with_expression(midi_path, expression_params)?;
 
     // 2. Apply GPU effects if enabled
     let processed_samples = if use_gpu {
         let mut effects = MetalEffectsProcessor::new()?;
-        effects.process(&audio_samples, reverb)?
+        effects.process(&audio_samples, reverb, reverb_ir_path, granular, Some(granular_params))?
     } else {
         audio_samples
     };
@@ -80,10 +137,103 @@ fn synthesize_midi_internal(
     Ok(duration)
 }
 
+/// Synthesize a MIDI file and return the raw interleaved stereo samples
+/// directly, instead of writing a WAV file. Lets pipelines (pitch models,
+/// notebooks, streaming) consume the audio at whatever rate and loudness
+/// they need without a file round-trip.
+///
+/// Args:
+///     midi_path: Path to input MIDI file
+///     soundfont_path: Path to SoundFont (.sf2) file
+///     sample_rate: Sample rate the synth renders at, in Hz (default: 44100)
+///     use_gpu: Enable Metal GPU effects (default: true)
+///     reverb: Enable reverb effect (default: true)
+///     reverb_ir_path: Optional impulse-response WAV for convolution reverb
+///     target_sample_rate: If set, band-limited-resample the output to this rate
+///         (e.g. 16000 for ML/notebook use) instead of `sample_rate`
+///     normalize: Optional normalization mode, "peak" or "rms" (default: None)
+///     normalize_target: Target level for `normalize`, 0.0-1.0 (default: 0.95)
+///
+/// Returns:
+///     Interleaved stereo samples as a flat list of floats (numpy-compatible
+///     via `numpy.array(result, dtype=numpy.float32)`)
+#[pyfunction]
+#[pyo3(signature = (
+    midi_path, soundfont_path, sample_rate=44100, use_gpu=true, reverb=true,
+    reverb_ir_path=None, target_sample_rate=None, normalize=None, normalize_target=0.95
+))]
+#[allow(clippy::too_many_arguments)]
+fn synthesize_midi_to_array(
+    midi_path: String,
+    soundfont_path: String,
+    sample_rate: u32,
+    use_gpu: bool,
+    reverb: bool,
+    reverb_ir_path: Option<String>,
+    target_sample_rate: Option<u32>,
+    normalize: Option<String>,
+    normalize_target: f32,
+) -> PyResult<Vec<f32>> {
+    let normalization = match normalize.as_deref() {
+        None => Normalization::None,
+        Some("peak") => Normalization::Peak { target: normalize_target },
+        Some("rms") => Normalization::Rms { target: normalize_target },
+        Some(other) => {
+            return Err(PyRuntimeError::new_err(format!(
+                "Unknown normalize mode '{}', expected 'peak' or 'rms'",
+                other
+            )))
+        }
+    };
+
+    synthesize_midi_to_array_internal(
+        &midi_path,
+        &soundfont_path,
+        sample_rate,
+        use_gpu,
+        reverb,
+        reverb_ir_path.as_deref(),
+        target_sample_rate,
+        normalization,
+    )
+    .map_err(|e| PyRuntimeError::new_err(format!("Synthesis failed: {}", e)))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn synthesize_midi_to_array_internal(
+    midi_path: &str,
+    soundfont_path: &str,
+    sample_rate: u32,
+    use_gpu: bool,
+    reverb: bool,
+    reverb_ir_path: Option<&str>,
+    target_sample_rate: Option<u32>,
+    normalization: Normalization,
+) -> Result<Vec<f32>> {
+    let mut synthesizer = MidiSynthesizer::new(soundfont_path, sample_rate)?;
+    let audio_samples = synthesizer.synthesize_file(midi_path)?;
+
+    let processed_samples = if use_gpu {
+        let mut effects = MetalEffectsProcessor::new()?;
+        effects.process(&audio_samples, reverb, reverb_ir_path, false, None)?
+    } else {
+        audio_samples
+    };
+
+    let mut processed_samples = match target_sample_rate {
+        Some(target_rate) => output::resample(&processed_samples, 2, sample_rate, target_rate)?,
+        None => processed_samples,
+    };
+
+    output::normalize(&mut processed_samples, normalization);
+
+    Ok(processed_samples)
+}
+
 /// Generate waveform image from audio file
 ///
 /// Args:
-///     audio_path: Path to audio file (WAV)
+///     audio_path: Path to audio file (WAV, MP3, FLAC, OGG Vorbis, ...)
 ///     width: Image width in pixels
 ///     height: Image height in pixels
 ///     use_gpu: Use Metal GPU for faster processing
@@ -104,12 +254,170 @@ fn generate_waveform(
         .map_err(|e| PyRuntimeError::new_err(format!("Waveform generation failed: {}", e)))
 }
 
-/// Detect pitch in audio samples using YIN algorithm
+fn parse_sampling_method(sampling: Option<&str>) -> PyResult<SamplingMethod> {
+    match sampling {
+        None | Some("peak") => Ok(SamplingMethod::Peak),
+        Some("rms") => Ok(SamplingMethod::Rms),
+        Some(other) => Err(PyRuntimeError::new_err(format!(
+            "Unknown sampling method '{}', expected 'peak' or 'rms'",
+            other
+        ))),
+    }
+}
+
+fn parse_channel_mode(channel_mode: Option<&str>) -> PyResult<ChannelMode> {
+    match channel_mode {
+        None | Some("mono") => Ok(ChannelMode::Mono),
+        Some("stacked") => Ok(ChannelMode::Stacked),
+        Some(other) => Err(PyRuntimeError::new_err(format!(
+            "Unknown channel mode '{}', expected 'mono' or 'stacked'",
+            other
+        ))),
+    }
+}
+
+/// Generate a waveform image with full control over color, amplitude range,
+/// sampling method, channel layout, and (optionally) a sub-range of the file —
+/// the knobs `generate_waveform` doesn't expose
+///
+/// Args:
+///     audio_path: Path to audio file (WAV, MP3, FLAC, OGG Vorbis, ...)
+///     width: Image width in pixels
+///     height: Image height in pixels
+///     use_gpu: Use Metal GPU for faster processing
+///     foreground: Foreground color, `"#RRGGBB"`/`"#RRGGBBAA"` or `"transparent"` (default: black)
+///     background: Background color, same format (default: white)
+///     amp_min: Lower bound of the amplitude range peaks are clamped to before
+///         being scaled to pixel rows, for zooming into quiet passages (default: -1.0)
+///     amp_max: Upper bound of the amplitude range (default: 1.0)
+///     sampling: "peak" (default) or "rms"
+///     channel_mode: "mono" (default, averages all channels) or "stacked"
+///         (one horizontal band per channel)
+///     range_start: Optional start of a sub-range to render instead of the whole file
+///     range_end: Optional end of the sub-range (both must be set together)
+///     range_in_samples: If true, `range_start`/`range_end` are raw sample
+///         indices; otherwise they're seconds (default: false)
+///
+/// Returns:
+///     PNG image as bytes
+#[pyfunction]
+#[pyo3(signature = (
+    audio_path, width=1000, height=200, use_gpu=true,
+    foreground=None, background=None, amp_min=-1.0, amp_max=1.0,
+    sampling=None, channel_mode=None,
+    range_start=None, range_end=None, range_in_samples=false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn generate_waveform_advanced(
+    audio_path: String,
+    width: u32,
+    height: u32,
+    use_gpu: bool,
+    foreground: Option<String>,
+    background: Option<String>,
+    amp_min: f32,
+    amp_max: f32,
+    sampling: Option<String>,
+    channel_mode: Option<String>,
+    range_start: Option<f64>,
+    range_end: Option<f64>,
+    range_in_samples: bool,
+) -> PyResult<Vec<u8>> {
+    let mut config = WaveformConfig {
+        amp_min,
+        amp_max,
+        sampling: parse_sampling_method(sampling.as_deref())?,
+        channel_mode: parse_channel_mode(channel_mode.as_deref())?,
+        ..Default::default()
+    };
+    if let Some(fg) = foreground {
+        config.foreground = fg;
+    }
+    if let Some(bg) = background {
+        config.background = bg;
+    }
+
+    let generator = WaveformGenerator::new();
+    let result = match (range_start, range_end) {
+        (Some(start), Some(end)) => {
+            let range = if range_in_samples {
+                Range::Samples(start as usize, end as usize)
+            } else {
+                Range::Seconds(start, end)
+            };
+            generator.generate_with_range(&audio_path, width, height, use_gpu, range, &config)
+        }
+        _ => generator.generate_with_config(&audio_path, width, height, use_gpu, &config),
+    };
+
+    result.map_err(|e| PyRuntimeError::new_err(format!("Waveform generation failed: {}", e)))
+}
+
+/// Decodes an audio file once and precomputes a waveform mip pyramid, so the
+/// same audio can be rendered at many widths/zoom levels without re-decoding
+/// or rescanning the raw samples each time. Wraps `waveform::BinnedWaveformRenderer`.
+#[pyclass]
+struct BinnedWaveform {
+    inner: BinnedWaveformRenderer,
+}
+
+#[pymethods]
+impl BinnedWaveform {
+    /// Decode `audio_path` (WAV, MP3, FLAC, OGG Vorbis, ...) and build the mip pyramid
+    #[new]
+    fn new(audio_path: String) -> PyResult<Self> {
+        let audio = AudioData::from_file(&audio_path)
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to load audio: {}", e)))?;
+        Ok(Self {
+            inner: BinnedWaveformRenderer::new(&audio),
+        })
+    }
+
+    /// Render this audio's waveform at `width` x `height` pixels
+    ///
+    /// Returns:
+    ///     PNG image as bytes
+    #[pyo3(signature = (
+        width, height, foreground=None, background=None,
+        amp_min=-1.0, amp_max=1.0, sampling=None,
+    ))]
+    fn render(
+        &self,
+        width: u32,
+        height: u32,
+        foreground: Option<String>,
+        background: Option<String>,
+        amp_min: f32,
+        amp_max: f32,
+        sampling: Option<String>,
+    ) -> PyResult<Vec<u8>> {
+        let mut config = WaveformConfig {
+            amp_min,
+            amp_max,
+            sampling: parse_sampling_method(sampling.as_deref())?,
+            ..Default::default()
+        };
+        if let Some(fg) = foreground {
+            config.foreground = fg;
+        }
+        if let Some(bg) = background {
+            config.background = bg;
+        }
+
+        self.inner
+            .render(width, height, &config)
+            .map_err(|e| PyRuntimeError::new_err(format!("Waveform generation failed: {}", e)))
+    }
+}
+
+/// Detect pitch in audio samples
 ///
 /// Args:
 ///     audio_samples: Audio samples as Vec<f32> (mono, normalized ±1.0)
 ///     sample_rate: Sample rate in Hz (default: 44100)
 ///     use_gpu: Reserved for future GPU implementation (currently unused)
+///     algorithm: "yin" (default) or "autocorrelation" — YIN can octave-error
+///         on rich piano tones; autocorrelation is a cheaper cross-check
 ///
 /// Returns:
 ///     Dictionary with pitch detection results or None if no pitch detected
@@ -122,22 +430,34 @@ fn generate_waveform(
 ///         "note_name": str         // e.g., "A4"
 ///     }
 #[pyfunction]
-#[pyo3(signature = (audio_samples, sample_rate=44100, use_gpu=false))]
+#[pyo3(signature = (audio_samples, sample_rate=44100, use_gpu=false, algorithm=None))]
 fn detect_pitch(
     py: pyo3::Python,
     audio_samples: Vec<f32>,
     sample_rate: u32,
     use_gpu: bool,
+    algorithm: Option<String>,
 ) -> PyResult<Option<pyo3::Py<pyo3::types::PyDict>>> {
     // Note: use_gpu parameter reserved for future Metal GPU implementation
-    // Currently uses CPU-based YIN algorithm
+    // Currently uses CPU-based algorithms only
 
     let params = YinParams {
         sample_rate,
         ..Default::default()
     };
 
-    match detect_pitch_yin(&audio_samples, &params) {
+    let algo = match algorithm.as_deref() {
+        None | Some("yin") => analyzer::PitchAlgorithm::Yin,
+        Some("autocorrelation") => analyzer::PitchAlgorithm::Autocorrelation,
+        Some(other) => {
+            return Err(PyRuntimeError::new_err(format!(
+                "Unknown pitch algorithm '{}', expected 'yin' or 'autocorrelation'",
+                other
+            )))
+        }
+    };
+
+    match analyzer::detect_pitch(&audio_samples, &params, algo) {
         Some(result) => {
             let dict = pyo3::types::PyDict::new_bound(py);
             dict.set_item("frequency", result.frequency)?;
@@ -203,24 +523,385 @@ fn detect_onsets_python(
     Ok(result)
 }
 
+/// Detect the global key and mode (major/minor) of a passage
+///
+/// Args:
+///     audio_samples: Audio samples as Vec<f32> (mono, normalized ±1.0)
+///     sample_rate: Sample rate in Hz (default: 44100)
+///
+/// Returns:
+///     Dictionary with key detection results
+///     {
+///         "tonic": int,        // pitch class 0-11 (0 = C)
+///         "tonic_name": str,   // e.g. "C", "F#"
+///         "mode": str,         // "major" or "minor"
+///         "confidence": float  // Pearson correlation of the winning profile
+///     }
+#[pyfunction]
+#[pyo3(signature = (audio_samples, sample_rate=44100))]
+fn detect_key(
+    py: pyo3::Python,
+    audio_samples: Vec<f32>,
+    sample_rate: u32,
+) -> PyResult<pyo3::Py<pyo3::types::PyDict>> {
+    let result = analyzer::detect_key(&audio_samples, sample_rate);
+
+    const PITCH_CLASS_NAMES: [&str; 12] = [
+        "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+    ];
+
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("tonic", result.tonic)?;
+    dict.set_item("tonic_name", PITCH_CLASS_NAMES[result.tonic as usize])?;
+    dict.set_item(
+        "mode",
+        match result.mode {
+            Mode::Major => "major",
+            Mode::Minor => "minor",
+        },
+    )?;
+    dict.set_item("confidence", result.confidence)?;
+
+    Ok(dict.unbind())
+}
+
+/// Detect up to `max_notes` simultaneous notes (a chord) via Harmonic Product Spectrum
+///
+/// Args:
+///     audio_samples: Audio samples as Vec<f32> (mono, normalized ±1.0)
+///     sample_rate: Sample rate in Hz (default: 44100)
+///     max_notes: Maximum number of simultaneous notes to return (default: 6)
+///
+/// Returns:
+///     List of dictionaries, strongest note first
+///     [
+///         {
+///             "frequency": float,
+///             "confidence": float,
+///             "midi_note": int,
+///             "cents_offset": float,
+///             "rms_level": float,
+///             "note_name": str
+///         },
+///         ...
+///     ]
+#[pyfunction]
+#[pyo3(signature = (audio_samples, sample_rate=44100, max_notes=6))]
+fn detect_chord(
+    py: pyo3::Python,
+    audio_samples: Vec<f32>,
+    sample_rate: u32,
+    max_notes: usize,
+) -> PyResult<Vec<pyo3::Py<pyo3::types::PyDict>>> {
+    let params = analyzer::ChordParams {
+        sample_rate,
+        max_notes,
+        ..Default::default()
+    };
+
+    let notes = analyzer::detect_chord(&audio_samples, &params);
+
+    let mut result = Vec::new();
+    for note in notes {
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("frequency", note.frequency)?;
+        dict.set_item("confidence", note.confidence)?;
+        dict.set_item("midi_note", note.midi_note)?;
+        dict.set_item("cents_offset", note.cents_offset)?;
+        dict.set_item("rms_level", note.rms_level)?;
+        dict.set_item("note_name", analyzer::midi_to_note_name(note.midi_note))?;
+        result.push(dict.unbind());
+    }
+
+    Ok(result)
+}
+
+/// Estimate tempo (BPM) from audio by detecting onsets and folding their
+/// inter-onset intervals into a BPM histogram
+///
+/// Args:
+///     audio_samples: Audio samples as Vec<f32> (mono, normalized ±1.0)
+///     sample_rate: Sample rate in Hz (default: 44100)
+///     min_bpm: Lower bound of the tempo search range (default: 40.0)
+///     max_bpm: Upper bound of the tempo search range (default: 240.0)
+///
+/// Returns:
+///     Dictionary, or None if fewer than two onsets were detected
+///     {
+///         "bpm": float,
+///         "confidence": float,   // peak prominence over the histogram mean
+///         "beat_phase": float    // seconds from t=0 to the first inferred beat
+///     }
+#[pyfunction]
+#[pyo3(signature = (audio_samples, sample_rate=44100, min_bpm=40.0, max_bpm=240.0))]
+fn estimate_tempo(
+    py: pyo3::Python,
+    audio_samples: Vec<f32>,
+    sample_rate: u32,
+    min_bpm: f32,
+    max_bpm: f32,
+) -> PyResult<Option<pyo3::Py<pyo3::types::PyDict>>> {
+    let onset_params = OnsetParams {
+        sample_rate,
+        ..Default::default()
+    };
+    let onsets = detect_onsets(&audio_samples, &onset_params);
+
+    let tempo_params = analyzer::TempoParams {
+        min_bpm,
+        max_bpm,
+        ..Default::default()
+    };
+
+    let result = match analyzer::estimate_tempo(&onsets, &tempo_params) {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("bpm", result.bpm)?;
+    dict.set_item("confidence", result.confidence)?;
+    dict.set_item("beat_phase", result.beat_phase)?;
+
+    Ok(Some(dict.unbind()))
+}
+
 /// Analyze audio performance against expected MIDI
 ///
 /// Args:
 ///     recording_path: Path to recorded audio (WAV)
 ///     expected_midi_path: Path to expected MIDI file
-///     use_gpu: Use Metal GPU for FFT analysis
+///     _use_gpu: Reserved for future Metal GPU-accelerated analysis (currently unused)
 ///
 /// Returns:
-///     JSON string with analysis results
+///     JSON string `{"pitch_accuracy": float, "rhythm_accuracy": float, "note_errors": [...]}`
 #[pyfunction]
-#[pyo3(signature = (_recording_path, _expected_midi_path, _use_gpu=true))]
+#[pyo3(signature = (recording_path, expected_midi_path, _use_gpu=true))]
 fn analyze_performance(
-    _recording_path: String,
-    _expected_midi_path: String,
+    recording_path: String,
+    expected_midi_path: String,
     _use_gpu: bool,
 ) -> PyResult<String> {
-    // TODO: Implement in future phase (STORY-2.3, 2.4)
-    Ok(r#"{"pitch_accuracy": 0.95, "rhythm_accuracy": 0.88}"#.to_string())
+    analyze_performance_internal(&recording_path, &expected_midi_path)
+        .map_err(|e| PyRuntimeError::new_err(format!("Performance analysis failed: {}", e)))
+}
+
+fn analyze_performance_internal(recording_path: &str, expected_midi_path: &str) -> Result<String> {
+    let mut reader = hound::WavReader::open(recording_path)
+        .context("Failed to open recording WAV file")?;
+    let spec = reader.spec();
+
+    let interleaved: Vec<f32> = if spec.sample_format == hound::SampleFormat::Float {
+        reader.samples::<f32>().map(|s| s.unwrap_or(0.0)).collect()
+    } else {
+        reader
+            .samples::<i16>()
+            .map(|s| s.unwrap_or(0) as f32 / 32768.0)
+            .collect()
+    };
+
+    let channels = spec.channels as usize;
+    let samples: Vec<f32> = if channels <= 1 {
+        interleaved
+    } else {
+        interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()
+    };
+
+    let expected_notes: Vec<analyzer::NoteEvent> = MidiSynthesizer::parse_note_events(expected_midi_path)?
+        .into_iter()
+        .map(|(time, midi_note)| analyzer::NoteEvent { time, midi_note })
+        .collect();
+
+    let score = analyzer::score_performance(&samples, spec.sample_rate, &expected_notes);
+
+    serde_json::to_string(&score).context("Failed to serialize performance score")
+}
+
+/// Real-time analyzer wrapping `analyzer::StreamingAnalyzer` for Python
+///
+/// Feed it successive blocks from a live audio callback via `push`; unlike
+/// `detect_pitch`/`detect_onsets_python`, onset timestamps and inter-onset
+/// gating persist across calls instead of resetting every time.
+#[pyclass]
+struct StreamingAnalyzer {
+    inner: analyzer::StreamingAnalyzer,
+}
+
+#[pymethods]
+impl StreamingAnalyzer {
+    #[new]
+    #[pyo3(signature = (sample_rate=44100))]
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            inner: analyzer::StreamingAnalyzer::new(sample_rate),
+        }
+    }
+
+    /// Feed a block of new audio samples and get back this push's events
+    ///
+    /// Returns:
+    ///     Dictionary `{"pitch": dict|None, "onsets": [dict, ...], "dynamics": dict|None}`
+    fn push(&mut self, py: pyo3::Python, block: Vec<f32>) -> PyResult<pyo3::Py<pyo3::types::PyDict>> {
+        let frame = self.inner.push(&block);
+        let dict = pyo3::types::PyDict::new_bound(py);
+
+        match frame.pitch {
+            Some(pitch) => {
+                let pitch_dict = pyo3::types::PyDict::new_bound(py);
+                pitch_dict.set_item("frequency", pitch.frequency)?;
+                pitch_dict.set_item("confidence", pitch.confidence)?;
+                pitch_dict.set_item("midi_note", pitch.midi_note)?;
+                pitch_dict.set_item("cents_offset", pitch.cents_offset)?;
+                pitch_dict.set_item("rms_level", pitch.rms_level)?;
+                pitch_dict.set_item("note_name", analyzer::midi_to_note_name(pitch.midi_note))?;
+                dict.set_item("pitch", pitch_dict)?;
+            }
+            None => dict.set_item("pitch", py.None())?,
+        }
+
+        let onsets = pyo3::types::PyList::empty_bound(py);
+        for onset in &frame.onsets {
+            let onset_dict = pyo3::types::PyDict::new_bound(py);
+            onset_dict.set_item("timestamp", onset.timestamp)?;
+            onset_dict.set_item("sample_index", onset.sample_index)?;
+            onset_dict.set_item("strength", onset.strength)?;
+            onset_dict.set_item("confidence", onset.confidence)?;
+            onsets.append(onset_dict)?;
+        }
+        dict.set_item("onsets", onsets)?;
+
+        match frame.dynamics {
+            Some(dynamics) => {
+                let dynamics_dict = pyo3::types::PyDict::new_bound(py);
+                dynamics_dict.set_item("timestamp", dynamics.timestamp)?;
+                dynamics_dict.set_item("rms_level", dynamics.rms_level)?;
+                dynamics_dict.set_item("peak_level", dynamics.peak_level)?;
+                dynamics_dict.set_item("db_level", dynamics.db_level)?;
+                dynamics_dict.set_item("midi_velocity", dynamics.midi_velocity)?;
+                dict.set_item("dynamics", dynamics_dict)?;
+            }
+            None => dict.set_item("dynamics", py.None())?,
+        }
+
+        Ok(dict.unbind())
+    }
+}
+
+/// Handle to a running live-play session, returned to Python by `start_live_session`
+///
+/// Keeps the MIDI input connection and audio output stream alive for as long
+/// as the handle is held; call `stop()` (or let it drop) to tear both down.
+#[pyclass]
+struct LiveSession {
+    handle: Option<LiveSessionHandle>,
+}
+
+#[pymethods]
+impl LiveSession {
+    /// Stop the live session, closing the MIDI input and audio output streams
+    fn stop(&mut self) -> PyResult<()> {
+        if let Some(handle) = self.handle.take() {
+            handle.stop();
+        }
+        Ok(())
+    }
+
+    /// Set the master volume (0.0-1.0)
+    fn set_master_volume(&self, volume: f32) -> PyResult<()> {
+        match &self.handle {
+            Some(handle) => {
+                handle.set_master_volume(volume);
+                Ok(())
+            }
+            None => Err(PyRuntimeError::new_err("Live session already stopped")),
+        }
+    }
+
+    /// Set a per-channel volume (0.0-1.0)
+    fn set_channel_volume(&self, channel: usize, volume: f32) -> PyResult<()> {
+        match &self.handle {
+            Some(handle) => {
+                handle.set_channel_volume(channel, volume);
+                Ok(())
+            }
+            None => Err(PyRuntimeError::new_err("Live session already stopped")),
+        }
+    }
+
+    /// Enable or disable the built-in metronome
+    fn set_metronome_enabled(&self, enabled: bool) -> PyResult<()> {
+        match &self.handle {
+            Some(handle) => {
+                handle.set_metronome_enabled(enabled);
+                Ok(())
+            }
+            None => Err(PyRuntimeError::new_err("Live session already stopped")),
+        }
+    }
+
+    /// Change the metronome tempo in beats per minute
+    fn set_metronome_bpm(&self, bpm: f32) -> PyResult<()> {
+        match &self.handle {
+            Some(handle) => {
+                handle.set_metronome_bpm(bpm);
+                Ok(())
+            }
+            None => Err(PyRuntimeError::new_err("Live session already stopped")),
+        }
+    }
+
+    /// Start capturing the session as a re-editable MIDI file and a finished WAV file
+    fn start_recording(&self, midi_out_path: String, wav_out_path: String) -> PyResult<()> {
+        match &self.handle {
+            Some(handle) => {
+                handle.start_recording(&midi_out_path, &wav_out_path);
+                Ok(())
+            }
+            None => Err(PyRuntimeError::new_err("Live session already stopped")),
+        }
+    }
+
+    /// Stop the active take, flushing both the MIDI and WAV files to disk
+    fn stop_recording(&self) -> PyResult<()> {
+        match &self.handle {
+            Some(handle) => handle
+                .stop_recording()
+                .map_err(|e| PyRuntimeError::new_err(format!("Failed to stop recording: {}", e))),
+            None => Err(PyRuntimeError::new_err("Live session already stopped")),
+        }
+    }
+}
+
+/// Start a live MIDI-in, audio-out practice session
+///
+/// Args:
+///     soundfont_path: Path to SoundFont (.sf2) file
+///     sample_rate: Sample rate in Hz (default: 44100)
+///     midi_input_name: Name of the MIDI input device to use (default: first available)
+///     metronome_bpm: Initial metronome tempo in beats per minute (default: 120.0)
+///
+/// Returns:
+///     A `LiveSession` handle; call `.stop()` to end the session
+#[pyfunction]
+#[pyo3(signature = (soundfont_path, sample_rate=44100, midi_input_name=None, metronome_bpm=120.0))]
+fn start_live_session(
+    soundfont_path: String,
+    sample_rate: u32,
+    midi_input_name: Option<String>,
+    metronome_bpm: f32,
+) -> PyResult<LiveSession> {
+    let handle = live::start_live_session(
+        &soundfont_path,
+        sample_rate,
+        midi_input_name.as_deref(),
+        metronome_bpm,
+    )
+    .map_err(|e| PyRuntimeError::new_err(format!("Failed to start live session: {}", e)))?;
+
+    Ok(LiveSession { handle: Some(handle) })
 }
 
 /// Helper function to write WAV file
@@ -248,9 +929,18 @@ fn write_wav(path: &str, samples: &[f32], sample_rate: u32) -> Result<()> {
 #[pymodule]
 fn rust_audio_engine(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(synthesize_midi, m)?)?;
+    m.add_function(wrap_pyfunction!(synthesize_midi_to_array, m)?)?;
     m.add_function(wrap_pyfunction!(generate_waveform, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_waveform_advanced, m)?)?;
     m.add_function(wrap_pyfunction!(detect_pitch, m)?)?;
     m.add_function(wrap_pyfunction!(detect_onsets_python, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_key, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_chord, m)?)?;
+    m.add_function(wrap_pyfunction!(estimate_tempo, m)?)?;
     m.add_function(wrap_pyfunction!(analyze_performance, m)?)?;
+    m.add_function(wrap_pyfunction!(start_live_session, m)?)?;
+    m.add_class::<LiveSession>()?;
+    m.add_class::<StreamingAnalyzer>()?;
+    m.add_class::<BinnedWaveform>()?;
     Ok(())
 }
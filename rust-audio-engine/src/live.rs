@@ -0,0 +1,330 @@
+//! Real-time MIDI input and live synthesis
+//!
+//! Opens a connected MIDI input device, feeds events into a `MidiSynthesizer`
+//! in real time, and streams the rendered stereo buffer to the default audio
+//! output device via `cpal`. Mirrors the shared-state + mutex pattern used by
+//! progmidi's live-play engine.
+
+use crate::recording::SessionRecorder;
+use crate::synthesizer::MidiSynthesizer;
+use anyhow::{Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use midir::{MidiInput, MidiInputConnection};
+use midly::{live::LiveEvent, MidiMessage};
+use std::sync::{Arc, Mutex};
+
+/// Number of MIDI channels tracked for sustain/volume state
+const NUM_CHANNELS: usize = 16;
+
+/// Sustain-pedal controller number (CC64, "damper pedal")
+const CC_SUSTAIN: u8 = 64;
+
+/// Channel Volume controller number (CC7), the same mechanism
+/// `synthesizer::apply_expression_params` uses to set initial channel volumes
+const CC_CHANNEL_VOLUME: u8 = 7;
+
+/// Shared note/state struct guarded by a mutex, mirroring progmidi's live engine
+struct LiveState {
+    synth: MidiSynthesizer,
+    /// Master volume (0.0-1.0)
+    master_volume: f32,
+    /// Sustain pedal held down, per channel
+    sustain_held: [bool; NUM_CHANNELS],
+    /// Notes released while sustain was held; replayed as NoteOff once CC64 releases
+    sustained_notes: [Vec<u8>; NUM_CHANNELS],
+    /// Metronome enabled and its tempo
+    metronome_enabled: bool,
+    metronome_bpm: f32,
+    /// Running sample counter used to schedule metronome clicks
+    samples_rendered: u64,
+    /// Active take, if `start_recording` has been called
+    recorder: Option<SessionRecorder>,
+}
+
+impl LiveState {
+    fn new(synth: MidiSynthesizer, bpm: f32) -> Self {
+        Self {
+            synth,
+            master_volume: 1.0,
+            sustain_held: [false; NUM_CHANNELS],
+            sustained_notes: Default::default(),
+            metronome_enabled: false,
+            metronome_bpm: bpm,
+            samples_rendered: 0,
+            recorder: None,
+        }
+    }
+
+    /// Handle one incoming MIDI message, applying sustain-pedal logic before
+    /// forwarding NoteOn/NoteOff/Controller/PitchBend to the synthesizer.
+    fn handle_event(&mut self, channel: u8, message: MidiMessage) {
+        let ch = channel as usize % NUM_CHANNELS;
+
+        match message {
+            MidiMessage::NoteOff { key, .. } => {
+                if self.sustain_held[ch] {
+                    // Hold the note until the pedal releases
+                    self.sustained_notes[ch].push(key.as_int());
+                } else {
+                    self.emit(channel, message);
+                }
+            }
+            MidiMessage::Controller { controller, value } if controller.as_int() == CC_SUSTAIN => {
+                let now_held = value.as_int() >= 64;
+                if self.sustain_held[ch] && !now_held {
+                    // Pedal released: flush any notes held during sustain
+                    for key in self.sustained_notes[ch].drain(..) {
+                        self.emit(
+                            channel,
+                            MidiMessage::NoteOff {
+                                key: key.into(),
+                                vel: 0.into(),
+                            },
+                        );
+                    }
+                }
+                self.sustain_held[ch] = now_held;
+                self.emit(channel, message);
+            }
+            _ => {
+                self.emit(channel, message);
+            }
+        }
+    }
+
+    /// Forward a MIDI message to the synthesizer and, if a take is active,
+    /// capture it into the MIDI recording with its current delta timing.
+    fn emit(&mut self, channel: u8, message: MidiMessage) {
+        self.synth.handle_midi_message(channel, &message);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.push_midi_event(channel, message);
+        }
+    }
+
+    /// Set a per-channel volume (0.0-1.0) by sending MIDI CC7 (Channel
+    /// Volume) to the synthesizer, the same mechanism
+    /// `synthesizer::apply_expression_params` uses to set initial channel
+    /// volumes — rustysynth honors this per-channel, unlike a post-render gain.
+    fn set_channel_volume(&mut self, channel: usize, volume: f32) {
+        if channel >= NUM_CHANNELS {
+            return;
+        }
+        let value = (volume.clamp(0.0, 1.0) * 127.0) as u8;
+        self.synth.handle_midi_message(
+            channel as u8,
+            &MidiMessage::Controller {
+                controller: CC_CHANNEL_VOLUME.into(),
+                value: value.into(),
+            },
+        );
+    }
+
+    /// Render one audio block, applying the master volume as a post-render
+    /// gain and mixing in the metronome click, then return interleaved
+    /// stereo samples. Per-channel volume is applied separately, via MIDI
+    /// CC7 in `set_channel_volume`, not as a gain here.
+    fn render_block(&mut self, frames: usize) -> Vec<f32> {
+        let (left, right) = self.synth.render_block(frames);
+        let sample_rate = self.synth.sample_rate();
+
+        let gain = self.master_volume;
+
+        let mut interleaved = Vec::with_capacity(frames * 2);
+        for i in 0..frames {
+            let mut l = left[i] * gain;
+            let mut r = right[i] * gain;
+
+            if self.metronome_enabled {
+                let click = self.metronome_click_at(self.samples_rendered + i as u64, sample_rate);
+                l += click;
+                r += click;
+            }
+
+            interleaved.push(l);
+            interleaved.push(r);
+        }
+
+        self.samples_rendered += frames as u64;
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.push_audio_block(&interleaved);
+        }
+
+        interleaved
+    }
+
+    /// Begin capturing the session as a MIDI + WAV take
+    fn start_recording(&mut self, midi_out_path: &str, wav_out_path: &str) {
+        const TICKS_PER_QUARTER: u16 = 480;
+        const TEMPO_US_PER_QUARTER: u32 = 500_000; // 120 BPM
+        self.recorder = Some(SessionRecorder::new(
+            midi_out_path,
+            wav_out_path,
+            self.synth.sample_rate(),
+            TICKS_PER_QUARTER,
+            TEMPO_US_PER_QUARTER,
+        ));
+    }
+
+    /// Stop the active take, flushing the MIDI and WAV files to disk
+    fn stop_recording(&mut self) -> Result<()> {
+        if let Some(recorder) = self.recorder.take() {
+            recorder.stop()?;
+        }
+        Ok(())
+    }
+
+    /// Short click at each beat boundary for the given BPM
+    fn metronome_click_at(&self, sample: u64, sample_rate: u32) -> f32 {
+        let samples_per_beat = (60.0 / self.metronome_bpm as f64 * sample_rate as f64) as u64;
+        if samples_per_beat == 0 {
+            return 0.0;
+        }
+        let phase = sample % samples_per_beat;
+        const CLICK_LEN: u64 = 200; // ~4.5ms at 44.1kHz
+        if phase < CLICK_LEN {
+            let decay = (-(phase as f32) / 30.0).exp();
+            0.3 * decay
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Handle to a running live-play session. Dropping or calling `stop` tears
+/// down both the MIDI input connection and the audio output stream.
+pub struct LiveSessionHandle {
+    _midi_connection: MidiInputConnection<()>,
+    _output_stream: cpal::Stream,
+    state: Arc<Mutex<LiveState>>,
+}
+
+impl LiveSessionHandle {
+    /// Stop the session: closes the MIDI input and audio output streams.
+    pub fn stop(self) {
+        // Dropping `self` closes the connection/stream via their Drop impls.
+    }
+
+    /// Set the master volume (0.0-1.0) while the session is running
+    pub fn set_master_volume(&self, volume: f32) {
+        self.state.lock().unwrap().master_volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// Set a per-channel volume (0.0-1.0) while the session is running
+    pub fn set_channel_volume(&self, channel: usize, volume: f32) {
+        self.state.lock().unwrap().set_channel_volume(channel, volume);
+    }
+
+    /// Enable or disable the built-in metronome
+    pub fn set_metronome_enabled(&self, enabled: bool) {
+        self.state.lock().unwrap().metronome_enabled = enabled;
+    }
+
+    /// Change the metronome tempo in beats per minute
+    pub fn set_metronome_bpm(&self, bpm: f32) {
+        self.state.lock().unwrap().metronome_bpm = bpm.max(1.0);
+    }
+
+    /// Start capturing the session as a MIDI file + WAV file take
+    pub fn start_recording(&self, midi_out_path: &str, wav_out_path: &str) {
+        self.state.lock().unwrap().start_recording(midi_out_path, wav_out_path);
+    }
+
+    /// Stop the active take, flushing both files to disk
+    pub fn stop_recording(&self) -> Result<()> {
+        self.state.lock().unwrap().stop_recording()
+    }
+}
+
+/// Start a live MIDI-in, audio-out practice session.
+///
+/// Opens `midi_input_name` (or the first available MIDI input port if `None`),
+/// routes NoteOn/NoteOff/Controller/PitchBend events into a freshly created
+/// `MidiSynthesizer`, and streams the rendered stereo buffer to the system's
+/// default output device through `cpal`. Sustain pedal (CC64) is handled by
+/// holding NoteOffs until the pedal is released; an optional metronome can be
+/// toggled on the returned handle.
+pub fn start_live_session(
+    soundfont_path: &str,
+    sample_rate: u32,
+    midi_input_name: Option<&str>,
+    metronome_bpm: f32,
+) -> Result<LiveSessionHandle> {
+    let synth = MidiSynthesizer::new(soundfont_path, sample_rate)?;
+    let state = Arc::new(Mutex::new(LiveState::new(synth, metronome_bpm)));
+
+    let midi_connection = open_midi_input(state.clone(), midi_input_name)?;
+    let output_stream = open_output_stream(state.clone())?;
+    output_stream.play().context("Failed to start audio output stream")?;
+
+    Ok(LiveSessionHandle {
+        _midi_connection: midi_connection,
+        _output_stream: output_stream,
+        state,
+    })
+}
+
+/// Open the MIDI input port named `midi_input_name` (or the first port if
+/// `None`) and forward incoming events into `state`
+fn open_midi_input(state: Arc<Mutex<LiveState>>, midi_input_name: Option<&str>) -> Result<MidiInputConnection<()>> {
+    let midi_in = MidiInput::new("gospel-keys live input").context("Failed to create MIDI input")?;
+    let ports = midi_in.ports();
+    let port = match midi_input_name {
+        Some(name) => ports
+            .iter()
+            .find(|p| midi_in.port_name(p).map(|n| n == name).unwrap_or(false))
+            .with_context(|| format!("MIDI input device '{}' not found", name))?,
+        None => ports.first().context("No MIDI input devices connected")?,
+    };
+
+    let port_name = midi_in.port_name(port).unwrap_or_else(|_| "unknown".to_string());
+
+    midi_in
+        .connect(
+            port,
+            "gospel-keys-live",
+            move |_timestamp, message, _| {
+                if let Ok(LiveEvent::Midi { channel, message }) = LiveEvent::parse(message) {
+                    if let Ok(mut state) = state.lock() {
+                        state.handle_event(channel.as_int(), message);
+                    }
+                }
+            },
+            (),
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to connect to MIDI input '{}': {}", port_name, e))
+}
+
+/// Open the default `cpal` output device and stream rendered audio from `state`
+fn open_output_stream(state: Arc<Mutex<LiveState>>) -> Result<cpal::Stream> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .context("No default audio output device found")?;
+    let config = device
+        .default_output_config()
+        .context("Failed to get default output config")?;
+
+    let channels = config.channels() as usize;
+    let stream = device
+        .build_output_stream(
+            &config.into(),
+            move |data: &mut [f32], _info: &cpal::OutputCallbackInfo| {
+                let frames = data.len() / channels.max(1);
+                let rendered = state.lock().unwrap().render_block(frames);
+
+                for (i, frame) in data.chunks_mut(channels).enumerate() {
+                    let l = rendered.get(i * 2).copied().unwrap_or(0.0);
+                    let r = rendered.get(i * 2 + 1).copied().unwrap_or(0.0);
+                    for (ch, sample) in frame.iter_mut().enumerate() {
+                        *sample = if ch % 2 == 0 { l } else { r };
+                    }
+                }
+            },
+            |err| eprintln!("Audio output stream error: {}", err),
+            None,
+        )
+        .context("Failed to build audio output stream")?;
+
+    Ok(stream)
+}
@@ -9,12 +9,60 @@
 use anyhow::{Context, Result};
 use metal::*;
 use core_foundation::base::TCFType;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use rustfft::num_complex::Complex;
+use std::collections::VecDeque;
 use std::mem;
+use std::sync::Arc;
+
+/// Block size (taps per partition) used for partitioned convolution reverb
+const CONVOLUTION_BLOCK_SIZE: usize = 2048;
+
+/// Assumed sample rate for effects that need to convert milliseconds/Hz to
+/// sample counts. `MetalEffectsProcessor` (unlike `MidiSynthesizer`) is not
+/// constructed with a sample rate today, so effects that care about it use
+/// this constant, matching the engine's default render rate.
+const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+/// Parameters controlling the granular sustain/pad effect
+///
+/// Captures short, Hann-windowed grains from the input and re-triggers them
+/// to build a sustained, pad-like texture out of held notes.
+#[derive(Debug, Clone)]
+pub struct GranularParams {
+    /// Length of each grain in milliseconds (typically 20-80ms)
+    pub grain_size_ms: f32,
+    /// How many grains are triggered per second (controls density/thickness)
+    pub density_hz: f32,
+    /// Fraction of each grain's length that overlaps the next (0.0-0.95)
+    pub overlap: f32,
+    /// Playback-rate offset applied when reading each grain from the source
+    /// (1.0 = original pitch, 2.0 = one octave up, 0.5 = one octave down)
+    pub pitch_ratio: f32,
+    /// Overall wet/dry mix (0.0 = dry only, 1.0 = fully granular)
+    pub mix: f32,
+}
+
+impl Default for GranularParams {
+    fn default() -> Self {
+        Self {
+            grain_size_ms: 40.0,
+            density_hz: 20.0,
+            overlap: 0.5,
+            pitch_ratio: 1.0,
+            mix: 0.5,
+        }
+    }
+}
 
 pub struct MetalEffectsProcessor {
     device: Device,
     command_queue: CommandQueue,
     reverb_pipeline: Option<ComputePipelineState>,
+    /// Per-bin complex multiply-accumulate kernel used by partitioned convolution reverb
+    partition_accumulate_pipeline: Option<ComputePipelineState>,
+    /// Grain scheduling + Hann windowing kernel used by the granular sustain effect
+    granular_pipeline: Option<ComputePipelineState>,
 }
 
 impl MetalEffectsProcessor {
@@ -34,88 +82,206 @@ impl MetalEffectsProcessor {
             device,
             command_queue,
             reverb_pipeline: None,
+            partition_accumulate_pipeline: None,
+            granular_pipeline: None,
         };
 
-        // Compile reverb shader on initialization
+        // Compile shaders on initialization
         processor.compile_reverb_shader()?;
+        processor.compile_partition_accumulate_shader()?;
+        processor.compile_granular_shader()?;
 
         Ok(processor)
     }
 
     /// Process audio samples with GPU effects
-    pub fn process(&mut self, samples: &[f32], enable_reverb: bool) -> Result<Vec<f32>> {
-        if !enable_reverb {
-            // No processing, return copy
-            return Ok(samples.to_vec());
+    ///
+    /// `reverb_ir_path`, when set, selects uniform-partitioned FFT convolution
+    /// against that impulse-response WAV instead of the synthetic Freeverb decay.
+    /// `granular`, when true, runs the granular sustain/pad effect afterwards
+    /// using `granular_params` (or the defaults if `None`).
+    pub fn process(
+        &mut self,
+        samples: &[f32],
+        enable_reverb: bool,
+        reverb_ir_path: Option<&str>,
+        granular: bool,
+        granular_params: Option<&GranularParams>,
+    ) -> Result<Vec<f32>> {
+        let mut output = if enable_reverb {
+            self.apply_reverb_gpu(samples, reverb_ir_path)?
+        } else {
+            samples.to_vec()
+        };
+
+        if granular {
+            let params = granular_params.cloned().unwrap_or_default();
+            output = self.apply_granular(&output, &params)?;
         }
 
-        // Apply GPU reverb
-        self.apply_reverb_gpu(samples)
+        Ok(output)
     }
 
-    /// Apply convolution reverb using Metal GPU
-    fn apply_reverb_gpu(&mut self, samples: &[f32]) -> Result<Vec<f32>> {
-        // Try GPU processing first, fallback to CPU if needed
-        if let Some(pipeline) = &self.reverb_pipeline {
-            self.apply_reverb_gpu_impl(samples, pipeline)
-                .or_else(|e| {
-                    eprintln!("GPU reverb failed, falling back to CPU: {}", e);
-                    self.apply_reverb_cpu(samples)
-                })
-        } else {
-            // No pipeline available, use CPU
-            self.apply_reverb_cpu(samples)
+    /// Apply reverb, preferring partitioned convolution against a real impulse
+    /// response when one is supplied, falling back to the CPU Freeverb path
+    /// if the IR can't be loaded or processing fails.
+    fn apply_reverb_gpu(&mut self, samples: &[f32], reverb_ir_path: Option<&str>) -> Result<Vec<f32>> {
+        if let Some(ir_path) = reverb_ir_path {
+            match self.apply_reverb_gpu_impl(samples, ir_path) {
+                Ok(wet) => return Ok(wet),
+                Err(e) => eprintln!("Convolution reverb failed, falling back to CPU Freeverb: {}", e),
+            }
         }
+
+        self.apply_reverb_cpu(samples)
     }
 
-    /// GPU implementation of algorithmic reverb
-    fn apply_reverb_gpu_impl(&self, samples: &[f32], pipeline: &ComputePipelineState) -> Result<Vec<f32>> {
-        // Create a simple impulse response for convolution
-        // This simulates a small room reverb
-        let impulse_length = 4410; // 100ms at 44.1kHz
-        let mut impulse = vec![0.0f32; impulse_length];
+    /// GPU-accelerated partitioned convolution reverb against a real impulse response
+    ///
+    /// Splits the impulse response into `CONVOLUTION_BLOCK_SIZE`-tap partitions
+    /// and FFTs each one once (CPU). For every incoming block: zero-pad to
+    /// `2 * CONVOLUTION_BLOCK_SIZE`, FFT it (CPU), push it onto a sliding ring
+    /// of the last K input spectra, then dispatch a Metal kernel with one
+    /// thread per frequency bin that sums `ring[k] * ir_partition[k]` across
+    /// all K partitions. The summed spectrum is inverse-FFT'd (CPU) and
+    /// overlap-added into the wet output. This takes the per-block cost from
+    /// O(N·M) time-domain taps down to O(N·log B) FFT work plus an
+    /// embarrassingly-parallel O(K) GPU reduction per bin.
+    fn apply_reverb_gpu_impl(&self, samples: &[f32], ir_path: &str) -> Result<Vec<f32>> {
+        let pipeline = self
+            .partition_accumulate_pipeline
+            .as_ref()
+            .context("Partition-accumulate pipeline not compiled")?;
+
+        let impulse_response = load_impulse_response(ir_path)?;
+        let block_size = CONVOLUTION_BLOCK_SIZE;
+        let fft_size = block_size * 2;
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let r2c = planner.plan_fft_forward(fft_size);
+        let c2r = planner.plan_fft_inverse(fft_size);
+
+        let ir_partitions = fft_ir_partitions(&impulse_response, block_size, fft_size, &r2c);
+        let num_partitions = ir_partitions.len();
+        let bins = ir_partitions[0].len();
+
+        let mut input_spectra: VecDeque<Vec<Complex<f32>>> =
+            VecDeque::from(vec![vec![Complex::new(0.0, 0.0); bins]; num_partitions]);
+        let mut overlap = vec![0.0f32; block_size];
+
+        let tail_blocks = num_partitions;
+        let total_output = samples.len() + impulse_response.len();
+        let mut wet = Vec::with_capacity(total_output);
+
+        let num_blocks = samples.len().div_ceil(block_size) + tail_blocks;
+
+        for block_idx in 0..num_blocks {
+            let start = block_idx * block_size;
+            let mut padded = vec![0.0f32; fft_size];
+            if start < samples.len() {
+                let end = (start + block_size).min(samples.len());
+                padded[..end - start].copy_from_slice(&samples[start..end]);
+            }
+
+            let mut spectrum = r2c.make_output_vec();
+            r2c.process(&mut padded, &mut spectrum).unwrap();
+
+            input_spectra.push_front(spectrum);
+            input_spectra.truncate(num_partitions);
+
+            let mut accumulated = self.accumulate_partitions_gpu(
+                pipeline,
+                &input_spectra,
+                &ir_partitions,
+                bins,
+            )?;
 
-        // Generate exponentially decaying impulse response
-        for i in 0..impulse_length {
-            let t = i as f32 / impulse_length as f32;
-            impulse[i] = (-t * 5.0).exp() * (1.0 - t) * 0.3; // 30% wet mix
+            let mut time_domain = vec![0.0f32; fft_size];
+            c2r.process(&mut accumulated, &mut time_domain).unwrap();
+
+            let norm = 1.0 / fft_size as f32;
+            let mut block_output = vec![0.0f32; block_size];
+            for i in 0..block_size {
+                block_output[i] = time_domain[i] * norm + overlap[i];
+            }
+            for i in 0..block_size {
+                overlap[i] = time_domain[block_size + i] * norm;
+            }
+
+            wet.extend_from_slice(&block_output);
         }
 
-        // Create Metal buffers
-        let input_buffer = create_buffer_from_slice(&self.device, samples);
-        let impulse_buffer = create_buffer_from_slice(&self.device, &impulse);
+        wet.truncate(total_output);
 
-        let output_length = samples.len();
+        const DRY: f32 = 0.8;
+        const WET: f32 = 0.3;
+
+        let mut output = vec![0.0f32; wet.len()];
+        for (i, out) in output.iter_mut().enumerate() {
+            let dry_sample = samples.get(i).copied().unwrap_or(0.0);
+            *out = dry_sample * DRY + wet[i] * WET;
+        }
+
+        Ok(output)
+    }
+
+    /// Sum `ring[k] * ir_partitions[k]` across all K partitions, per frequency
+    /// bin, on the GPU. One thread per bin; each thread loops over K partitions.
+    fn accumulate_partitions_gpu(
+        &self,
+        pipeline: &ComputePipelineState,
+        ring: &VecDeque<Vec<Complex<f32>>>,
+        ir_partitions: &[Vec<Complex<f32>>],
+        bins: usize,
+    ) -> Result<Vec<Complex<f32>>> {
+        let num_partitions = ir_partitions.len();
+
+        // Flatten to interleaved [re, im] float pairs, partition-major, for GPU upload
+        let mut ring_flat = vec![0.0f32; num_partitions * bins * 2];
+        let mut ir_flat = vec![0.0f32; num_partitions * bins * 2];
+
+        for k in 0..num_partitions {
+            let ring_spectrum = ring.get(k);
+            for b in 0..bins {
+                let base = (k * bins + b) * 2;
+                if let Some(spectrum) = ring_spectrum {
+                    ring_flat[base] = spectrum[b].re;
+                    ring_flat[base + 1] = spectrum[b].im;
+                }
+                ir_flat[base] = ir_partitions[k][b].re;
+                ir_flat[base + 1] = ir_partitions[k][b].im;
+            }
+        }
+
+        let ring_buffer = create_buffer_from_slice(&self.device, &ring_flat);
+        let ir_buffer = create_buffer_from_slice(&self.device, &ir_flat);
         let output_buffer = self.device.new_buffer(
-            (output_length * mem::size_of::<f32>()) as u64,
-            MTLResourceOptions::StorageModeShared
+            (bins * 2 * mem::size_of::<f32>()) as u64,
+            MTLResourceOptions::StorageModeShared,
         );
 
-        let input_len = samples.len() as u32;
-        let impulse_len = impulse.len() as u32;
-        let input_len_buffer = create_buffer_from_slice(&self.device, &[input_len]);
-        let impulse_len_buffer = create_buffer_from_slice(&self.device, &[impulse_len]);
+        let bins_u32 = bins as u32;
+        let partitions_u32 = num_partitions as u32;
+        let bins_buffer = create_buffer_from_slice(&self.device, &[bins_u32]);
+        let partitions_buffer = create_buffer_from_slice(&self.device, &[partitions_u32]);
 
-        // Create command buffer and encoder
         let command_buffer = self.command_queue.new_command_buffer();
         let encoder = command_buffer.new_compute_command_encoder();
 
         encoder.set_compute_pipeline_state(pipeline);
-        encoder.set_buffer(0, Some(&input_buffer), 0);
-        encoder.set_buffer(1, Some(&impulse_buffer), 0);
+        encoder.set_buffer(0, Some(&ring_buffer), 0);
+        encoder.set_buffer(1, Some(&ir_buffer), 0);
         encoder.set_buffer(2, Some(&output_buffer), 0);
-        encoder.set_buffer(3, Some(&input_len_buffer), 0);
-        encoder.set_buffer(4, Some(&impulse_len_buffer), 0);
+        encoder.set_buffer(3, Some(&bins_buffer), 0);
+        encoder.set_buffer(4, Some(&partitions_buffer), 0);
 
-        // Calculate thread groups
         let thread_group_size = MTLSize {
             width: pipeline.max_total_threads_per_threadgroup().min(256),
             height: 1,
             depth: 1,
         };
-
         let thread_groups = MTLSize {
-            width: (output_length as u64 + thread_group_size.width - 1) / thread_group_size.width,
+            width: (bins as u64 + thread_group_size.width - 1) / thread_group_size.width,
             height: 1,
             depth: 1,
         };
@@ -123,18 +289,19 @@ impl MetalEffectsProcessor {
         encoder.dispatch_thread_groups(thread_groups, thread_group_size);
         encoder.end_encoding();
 
-        // Execute and wait
         command_buffer.commit();
         command_buffer.wait_until_completed();
 
-        // Read results
-        let mut output = vec![0.0f32; output_length];
+        let mut flat_output = vec![0.0f32; bins * 2];
         unsafe {
             let ptr = output_buffer.contents() as *const f32;
-            std::ptr::copy_nonoverlapping(ptr, output.as_mut_ptr(), output_length);
+            std::ptr::copy_nonoverlapping(ptr, flat_output.as_mut_ptr(), bins * 2);
         }
 
-        Ok(output)
+        Ok(flat_output
+            .chunks(2)
+            .map(|c| Complex::new(c[0], c[1]))
+            .collect())
     }
 
     /// Simple CPU-based reverb (fallback)
@@ -186,7 +353,160 @@ impl MetalEffectsProcessor {
         Ok(output)
     }
 
-    /// Compile Metal shader for GPU reverb
+    /// Granular sustain/pad effect: captures short Hann-windowed grains from
+    /// `samples` and re-triggers them at `params.density_hz` to build a
+    /// sustained, pad-like texture. Prefers the GPU kernel, falling back to
+    /// an equivalent CPU implementation if the granular pipeline is missing
+    /// or the GPU pass errors.
+    fn apply_granular(&self, samples: &[f32], params: &GranularParams) -> Result<Vec<f32>> {
+        if let Some(pipeline) = &self.granular_pipeline {
+            match self.apply_granular_gpu(samples, params, pipeline) {
+                Ok(wet) => return Ok(wet),
+                Err(e) => eprintln!("GPU granular synthesis failed, falling back to CPU: {}", e),
+            }
+        }
+
+        self.apply_granular_cpu(samples, params)
+    }
+
+    /// Lay out grain scheduling (source offsets, output offsets, Hann window)
+    /// shared by both the GPU and CPU granular implementations.
+    ///
+    /// `density_hz` and `overlap` both push toward a denser texture, so
+    /// whichever implies the shorter hop (more grains per second) wins: a
+    /// high `density_hz` can shorten the hop below what `overlap` alone
+    /// would ask for, and vice versa. Source offsets track the output
+    /// 1:1 — `pitch_ratio` no longer touches grain placement, since it's
+    /// applied as an intra-grain read-rate in `apply_granular_cpu`/`_gpu` instead.
+    fn schedule_grains(samples_len: usize, params: &GranularParams) -> (usize, usize, Vec<f32>, Vec<f32>, Vec<u32>) {
+        let sample_rate = DEFAULT_SAMPLE_RATE as f32;
+        let grain_samples = ((params.grain_size_ms / 1000.0) * sample_rate).max(1.0) as usize;
+
+        let density_hop = (sample_rate / params.density_hz.max(0.1)).max(1.0);
+        let overlap_hop = grain_samples as f32 * (1.0 - params.overlap.clamp(0.0, 0.95)).max(0.05);
+        let hop = density_hop.min(overlap_hop).max(1.0) as usize;
+
+        let num_grains = samples_len / hop.max(1);
+
+        let window = hann_window(grain_samples);
+
+        let mut source_starts = Vec::with_capacity(num_grains);
+        let mut output_starts = Vec::with_capacity(num_grains);
+
+        for i in 0..num_grains {
+            let output_start = i * hop;
+            source_starts.push(output_start as f32);
+            output_starts.push(output_start as u32);
+        }
+
+        (grain_samples, num_grains, window, source_starts, output_starts)
+    }
+
+    /// GPU implementation: one threadgroup per active grain, writing into a
+    /// shared output accumulator via atomic adds.
+    fn apply_granular_gpu(
+        &self,
+        samples: &[f32],
+        params: &GranularParams,
+        pipeline: &ComputePipelineState,
+    ) -> Result<Vec<f32>> {
+        let (grain_samples, num_grains, window, source_starts, output_starts) =
+            Self::schedule_grains(samples.len(), params);
+
+        if num_grains == 0 {
+            return Ok(samples.to_vec());
+        }
+
+        let input_buffer = create_buffer_from_slice(&self.device, samples);
+        let window_buffer = create_buffer_from_slice(&self.device, &window);
+        let source_starts_buffer = create_buffer_from_slice(&self.device, &source_starts);
+        let output_starts_buffer = create_buffer_from_slice(&self.device, &output_starts);
+
+        let output_buffer = self.device.new_buffer(
+            (samples.len() * mem::size_of::<f32>()) as u64,
+            MTLResourceOptions::StorageModeShared,
+        );
+        // Zero-initialize the accumulator before atomic adds
+        unsafe {
+            std::ptr::write_bytes(output_buffer.contents() as *mut u8, 0, samples.len() * mem::size_of::<f32>());
+        }
+
+        let input_len = create_buffer_from_slice(&self.device, &[samples.len() as u32]);
+        let grain_len = create_buffer_from_slice(&self.device, &[grain_samples as u32]);
+        let pitch_ratio = create_buffer_from_slice(&self.device, &[params.pitch_ratio]);
+
+        let command_buffer = self.command_queue.new_command_buffer();
+        let encoder = command_buffer.new_compute_command_encoder();
+
+        encoder.set_compute_pipeline_state(pipeline);
+        encoder.set_buffer(0, Some(&input_buffer), 0);
+        encoder.set_buffer(1, Some(&window_buffer), 0);
+        encoder.set_buffer(2, Some(&source_starts_buffer), 0);
+        encoder.set_buffer(3, Some(&output_starts_buffer), 0);
+        encoder.set_buffer(4, Some(&output_buffer), 0);
+        encoder.set_buffer(5, Some(&input_len), 0);
+        encoder.set_buffer(6, Some(&grain_len), 0);
+        encoder.set_buffer(7, Some(&pitch_ratio), 0);
+
+        // One threadgroup per grain; threads stride over the grain's samples
+        // when the grain is longer than the threadgroup
+        let threads_per_group = MTLSize {
+            width: (grain_samples as u64).min(pipeline.max_total_threads_per_threadgroup()),
+            height: 1,
+            depth: 1,
+        };
+        let thread_groups = MTLSize {
+            width: num_grains as u64,
+            height: 1,
+            depth: 1,
+        };
+
+        encoder.dispatch_thread_groups(thread_groups, threads_per_group);
+        encoder.end_encoding();
+
+        command_buffer.commit();
+        command_buffer.wait_until_completed();
+
+        let mut granular = vec![0.0f32; samples.len()];
+        unsafe {
+            let ptr = output_buffer.contents() as *const f32;
+            std::ptr::copy_nonoverlapping(ptr, granular.as_mut_ptr(), samples.len());
+        }
+
+        Ok(mix_dry_wet(samples, &granular, params.mix))
+    }
+
+    /// CPU fallback for the granular sustain effect, mirroring the GPU kernel's scheduling
+    fn apply_granular_cpu(&self, samples: &[f32], params: &GranularParams) -> Result<Vec<f32>> {
+        let (grain_samples, num_grains, window, source_starts, output_starts) =
+            Self::schedule_grains(samples.len(), params);
+
+        let mut granular = vec![0.0f32; samples.len()];
+
+        for g in 0..num_grains {
+            let source_start = source_starts[g];
+            let output_start = output_starts[g] as usize;
+
+            for i in 0..grain_samples {
+                let dst_idx = output_start + i;
+                if dst_idx >= granular.len() {
+                    break;
+                }
+
+                // Reading each grain's contents at `pitch_ratio` samples per
+                // output sample (instead of 1:1) is what actually shifts the
+                // grain's pitch, rather than just scrubbing its start position.
+                let src_pos = source_start + i as f32 * params.pitch_ratio;
+                if let Some(sample) = interpolate_sample(samples, src_pos) {
+                    granular[dst_idx] += sample * window[i];
+                }
+            }
+        }
+
+        Ok(mix_dry_wet(samples, &granular, params.mix))
+    }
+
+    /// Compile Metal shader for the naive GPU reverb kernel
     fn compile_reverb_shader(&mut self) -> Result<()> {
         let shader_source = r#"
             #include <metal_stdlib>
@@ -229,6 +549,188 @@ impl MetalEffectsProcessor {
 
         Ok(())
     }
+
+    /// Compile the Metal shader used to sum per-partition complex products for
+    /// partitioned convolution reverb (one thread per frequency bin)
+    fn compile_partition_accumulate_shader(&mut self) -> Result<()> {
+        let shader_source = r#"
+            #include <metal_stdlib>
+            using namespace metal;
+
+            // Sums ring[k] * ir[k] (complex multiply) across all K partitions,
+            // one thread per frequency bin. Buffers are interleaved [re, im]
+            // floats, laid out partition-major: partition k's bin b starts at
+            // (k * bins + b) * 2.
+            kernel void accumulate_partitions(
+                device const float* ring [[buffer(0)]],
+                device const float* ir [[buffer(1)]],
+                device float* output [[buffer(2)]],
+                constant uint& bins [[buffer(3)]],
+                constant uint& num_partitions [[buffer(4)]],
+                uint bin [[thread_position_in_grid]]
+            ) {
+                if (bin >= bins) return;
+
+                float acc_re = 0.0;
+                float acc_im = 0.0;
+
+                for (uint k = 0; k < num_partitions; k++) {
+                    uint idx = (k * bins + bin) * 2;
+                    float a_re = ring[idx];
+                    float a_im = ring[idx + 1];
+                    float b_re = ir[idx];
+                    float b_im = ir[idx + 1];
+
+                    acc_re += a_re * b_re - a_im * b_im;
+                    acc_im += a_re * b_im + a_im * b_re;
+                }
+
+                output[bin * 2] = acc_re;
+                output[bin * 2 + 1] = acc_im;
+            }
+        "#;
+
+        let library = self.device
+            .new_library_with_source(shader_source, &CompileOptions::new())
+            .map_err(|e| anyhow::anyhow!("Failed to compile Metal shader: {}", e))?;
+
+        let kernel = library
+            .get_function("accumulate_partitions", None)
+            .map_err(|_| anyhow::anyhow!("Failed to get kernel function"))?;
+
+        let pipeline = self.device
+            .new_compute_pipeline_state_with_function(&kernel)
+            .map_err(|e| anyhow::anyhow!("Failed to create pipeline state: {}", e))?;
+
+        self.partition_accumulate_pipeline = Some(pipeline);
+        println!("Metal partition-accumulate shader compiled successfully");
+
+        Ok(())
+    }
+
+    /// Compile the Metal shader that schedules and windows grains for the
+    /// granular sustain effect: one threadgroup per active grain, each thread
+    /// handling every threadgroup-width-th sample of the grain and atomically
+    /// adding its Hann-windowed value into the shared output accumulator.
+    fn compile_granular_shader(&mut self) -> Result<()> {
+        let shader_source = r#"
+            #include <metal_stdlib>
+            using namespace metal;
+
+            // Re-triggers Hann-windowed grains into a shared output accumulator.
+            // threadgroup_position_in_grid selects the grain; thread_position_in_threadgroup
+            // selects the first sample offset within that grain, striding by the
+            // threadgroup width to cover longer grains. Each grain is read
+            // back at `pitch_ratio` source samples per output sample, with
+            // linear interpolation between neighboring samples — reading
+            // faster/slower than 1:1 is what shifts the grain's pitch.
+            kernel void granular_synthesis(
+                device const float* input [[buffer(0)]],
+                device const float* window [[buffer(1)]],
+                device const float* source_starts [[buffer(2)]],
+                device const uint* output_starts [[buffer(3)]],
+                device atomic_float* output [[buffer(4)]],
+                constant uint& input_length [[buffer(5)]],
+                constant uint& grain_length [[buffer(6)]],
+                constant float& pitch_ratio [[buffer(7)]],
+                uint grain_id [[threadgroup_position_in_grid]],
+                uint local_id [[thread_position_in_threadgroup]],
+                uint group_width [[threads_per_threadgroup]]
+            ) {
+                for (uint i = local_id; i < grain_length; i += group_width) {
+                    float src_pos = source_starts[grain_id] + float(i) * pitch_ratio;
+                    uint dst_idx = output_starts[grain_id] + i;
+
+                    if (dst_idx >= input_length) break;
+                    if (src_pos < 0.0) continue;
+
+                    uint src_idx0 = uint(src_pos);
+                    if (src_idx0 >= input_length) continue;
+
+                    float frac = src_pos - float(src_idx0);
+                    float s0 = input[src_idx0];
+                    float s1 = (src_idx0 + 1 < input_length) ? input[src_idx0 + 1] : s0;
+                    float sample = s0 + (s1 - s0) * frac;
+
+                    float value = sample * window[i];
+                    atomic_fetch_add_explicit(&output[dst_idx], value, memory_order_relaxed);
+                }
+            }
+        "#;
+
+        let library = self.device
+            .new_library_with_source(shader_source, &CompileOptions::new())
+            .map_err(|e| anyhow::anyhow!("Failed to compile Metal shader: {}", e))?;
+
+        let kernel = library
+            .get_function("granular_synthesis", None)
+            .map_err(|_| anyhow::anyhow!("Failed to get kernel function"))?;
+
+        let pipeline = self.device
+            .new_compute_pipeline_state_with_function(&kernel)
+            .map_err(|e| anyhow::anyhow!("Failed to create pipeline state: {}", e))?;
+
+        self.granular_pipeline = Some(pipeline);
+        println!("Metal granular synthesis shader compiled successfully");
+
+        Ok(())
+    }
+}
+
+/// FFT each `block_size`-tap partition of the impulse response (zero-padded
+/// to `fft_size`), once, up front.
+fn fft_ir_partitions(
+    impulse_response: &[f32],
+    block_size: usize,
+    fft_size: usize,
+    r2c: &Arc<dyn RealToComplex<f32>>,
+) -> Vec<Vec<Complex<f32>>> {
+    let num_partitions = impulse_response.len().div_ceil(block_size).max(1);
+    let mut partitions = Vec::with_capacity(num_partitions);
+
+    for k in 0..num_partitions {
+        let start = k * block_size;
+        let end = (start + block_size).min(impulse_response.len());
+
+        let mut padded = vec![0.0f32; fft_size];
+        if start < end {
+            padded[..end - start].copy_from_slice(&impulse_response[start..end]);
+        }
+
+        let mut spectrum = r2c.make_output_vec();
+        r2c.process(&mut padded, &mut spectrum).unwrap();
+        partitions.push(spectrum);
+    }
+
+    partitions
+}
+
+/// Load an impulse-response WAV file, downmixed to mono f32 samples in ±1.0
+fn load_impulse_response(path: &str) -> Result<Vec<f32>> {
+    let mut reader = hound::WavReader::open(path)
+        .with_context(|| format!("Failed to open impulse response file: {}", path))?;
+
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    let interleaved: Vec<f32> = if spec.sample_format == hound::SampleFormat::Float {
+        reader.samples::<f32>().map(|s| s.unwrap_or(0.0)).collect()
+    } else {
+        reader
+            .samples::<i16>()
+            .map(|s| s.unwrap_or(0) as f32 / 32768.0)
+            .collect()
+    };
+
+    if channels <= 1 {
+        return Ok(interleaved);
+    }
+
+    // Downmix to mono by averaging channels
+    Ok(interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect())
 }
 
 /// Helper to create Metal buffer from slice
@@ -243,3 +745,44 @@ fn create_buffer_from_slice<T>(device: &Device, data: &[T]) -> Buffer {
 
     buffer
 }
+
+/// Generate a Hann window of the given length, used to taper each grain's edges
+fn hann_window(size: usize) -> Vec<f32> {
+    if size <= 1 {
+        return vec![1.0; size];
+    }
+    (0..size)
+        .map(|n| {
+            let angle = 2.0 * std::f32::consts::PI * n as f32 / (size - 1) as f32;
+            0.5 * (1.0 - angle.cos())
+        })
+        .collect()
+}
+
+/// Linearly interpolate `samples` at a fractional index, mirroring the
+/// granular synthesis Metal kernel's read so the CPU fallback pitch-shifts
+/// grains identically. Returns `None` for positions outside the buffer.
+fn interpolate_sample(samples: &[f32], pos: f32) -> Option<f32> {
+    if pos < 0.0 {
+        return None;
+    }
+
+    let idx0 = pos as usize;
+    if idx0 >= samples.len() {
+        return None;
+    }
+
+    let frac = pos - idx0 as f32;
+    let s0 = samples[idx0];
+    let s1 = samples.get(idx0 + 1).copied().unwrap_or(s0);
+    Some(s0 + (s1 - s0) * frac)
+}
+
+/// Blend a dry signal with a processed (wet) signal of the same length
+fn mix_dry_wet(dry: &[f32], wet: &[f32], mix: f32) -> Vec<f32> {
+    let mix = mix.clamp(0.0, 1.0);
+    dry.iter()
+        .zip(wet.iter())
+        .map(|(&d, &w)| d * (1.0 - mix) + w * mix)
+        .collect()
+}
@@ -0,0 +1,150 @@
+//! Output-stage utilities: resampling, normalization, and raw-array export
+//!
+//! `write_wav` hard-codes stereo 16-bit PCM at whatever rate the synthesizer
+//! rendered at. These helpers sit between synthesis/effects and that final
+//! write (or replace it entirely), so pipelines that want a different target
+//! rate (e.g. 16 kHz for ML/notebook use), loudness-normalized output, or the
+//! raw interleaved buffer itself don't need a WAV round-trip to get it.
+
+use anyhow::{bail, Result};
+
+/// How to normalize an output buffer before use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalization {
+    /// Leave amplitudes untouched
+    None,
+    /// Scale so the loudest sample reaches `target` (0.0-1.0)
+    Peak { target: f32 },
+    /// Scale so the RMS level reaches `target` (0.0-1.0)
+    Rms { target: f32 },
+}
+
+/// Resample an interleaved multi-channel buffer from `from_rate` to `to_rate`
+/// using windowed-sinc (Lanczos) interpolation. Windowing the sinc keeps the
+/// result band-limited, which avoids the aliasing a naive linear resample
+/// would introduce when downsampling (e.g. 44.1 kHz -> 16 kHz for ML input).
+pub fn resample(samples: &[f32], channels: usize, from_rate: u32, to_rate: u32) -> Result<Vec<f32>> {
+    if channels == 0 {
+        bail!("channel count must be non-zero");
+    }
+    if from_rate == 0 || to_rate == 0 {
+        bail!("sample rates must be non-zero");
+    }
+    if from_rate == to_rate || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+
+    // Number of side lobes included in the windowed sinc kernel
+    const LANCZOS_A: i64 = 3;
+
+    let frames_in = samples.len() / channels;
+    let ratio = to_rate as f64 / from_rate as f64;
+    let frames_out = ((frames_in as f64) * ratio).round() as usize;
+    let mut out = vec![0.0f32; frames_out * channels];
+
+    // When downsampling, stretch the kernel by 1/ratio so its cutoff sits at
+    // the output Nyquist rather than the input's
+    let scale = ratio.min(1.0);
+    let half_width = (LANCZOS_A as f64 / scale).ceil() as i64;
+
+    for out_frame in 0..frames_out {
+        let src_pos = out_frame as f64 / ratio;
+        let src_floor = src_pos.floor() as i64;
+
+        for channel in 0..channels {
+            let mut acc = 0.0f64;
+            let mut weight_sum = 0.0f64;
+
+            for tap in (src_floor - half_width + 1)..=(src_floor + half_width) {
+                if tap < 0 || tap as usize >= frames_in {
+                    continue;
+                }
+                let weight = lanczos_kernel((src_pos - tap as f64) * scale, LANCZOS_A as f64);
+                acc += weight * samples[tap as usize * channels + channel] as f64;
+                weight_sum += weight;
+            }
+
+            out[out_frame * channels + channel] = if weight_sum.abs() > 1e-9 {
+                (acc / weight_sum) as f32
+            } else {
+                0.0
+            };
+        }
+    }
+
+    Ok(out)
+}
+
+fn lanczos_kernel(x: f64, a: f64) -> f64 {
+    if x == 0.0 {
+        return 1.0;
+    }
+    if x.abs() >= a {
+        return 0.0;
+    }
+    let pi_x = std::f64::consts::PI * x;
+    a * (pi_x.sin()) * (pi_x / a).sin() / (pi_x * pi_x)
+}
+
+/// Normalize an interleaved buffer in place according to `mode`
+pub fn normalize(samples: &mut [f32], mode: Normalization) {
+    match mode {
+        Normalization::None => {}
+        Normalization::Peak { target } => {
+            let peak = samples.iter().fold(0.0f32, |max, &s| max.max(s.abs()));
+            if peak > 1e-9 {
+                let gain = target / peak;
+                for s in samples.iter_mut() {
+                    *s *= gain;
+                }
+            }
+        }
+        Normalization::Rms { target } => {
+            let mean_square = samples.iter().map(|&s| (s as f64) * (s as f64)).sum::<f64>()
+                / samples.len().max(1) as f64;
+            let rms = mean_square.sqrt() as f32;
+            if rms > 1e-9 {
+                let gain = target / rms;
+                for s in samples.iter_mut() {
+                    *s *= gain;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_identity_when_rates_match() {
+        let samples = vec![0.1, 0.2, -0.3, 0.4];
+        let result = resample(&samples, 1, 44100, 44100).unwrap();
+        assert_eq!(result, samples);
+    }
+
+    #[test]
+    fn test_resample_changes_frame_count() {
+        let samples = vec![0.0f32; 4410]; // 100ms at 44.1kHz, mono
+        let result = resample(&samples, 1, 44100, 16000).unwrap();
+        let expected_frames = (4410.0 * 16000.0 / 44100.0).round() as usize;
+        assert_eq!(result.len(), expected_frames);
+    }
+
+    #[test]
+    fn test_peak_normalize_reaches_target() {
+        let mut samples = vec![0.1, -0.2, 0.05];
+        normalize(&mut samples, Normalization::Peak { target: 1.0 });
+        let peak = samples.iter().fold(0.0f32, |max, &s| max.max(s.abs()));
+        assert!((peak - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_normalize_none_is_noop() {
+        let samples = vec![0.1, -0.2, 0.05];
+        let mut copy = samples.clone();
+        normalize(&mut copy, Normalization::None);
+        assert_eq!(samples, copy);
+    }
+}
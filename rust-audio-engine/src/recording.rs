@@ -0,0 +1,173 @@
+//! Session recording: capture a live or file-based take as both a re-editable
+//! Standard MIDI File and a finished WAV, in one pass.
+//!
+//! Mirrors progmidi's `MidiRecording`/`WavRecording` split: MIDI events are
+//! captured with millisecond-delta timing and VLQ-encoded into an SMF, while
+//! the post-effects PCM stream is buffered and flushed to WAV on stop.
+
+use anyhow::{Context, Result};
+use midly::MidiMessage;
+use std::time::Instant;
+
+/// Captures MIDI events with delta timing and writes them out as a
+/// single-track Standard MIDI File.
+pub struct MidiRecording {
+    ticks_per_quarter: u16,
+    tempo_us_per_quarter: u32,
+    started_at: Instant,
+    last_event_ms: u64,
+    events: Vec<(u32, u8, MidiMessage)>, // (delta ticks, channel, message)
+}
+
+impl MidiRecording {
+    /// Start a new MIDI recording. `ticks_per_quarter` and `tempo_us_per_quarter`
+    /// control how wall-clock milliseconds are quantized into MIDI ticks.
+    pub fn new(ticks_per_quarter: u16, tempo_us_per_quarter: u32) -> Self {
+        Self {
+            ticks_per_quarter,
+            tempo_us_per_quarter,
+            started_at: Instant::now(),
+            last_event_ms: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Record a MIDI event at the current wall-clock time
+    pub fn push_event(&mut self, channel: u8, message: MidiMessage) {
+        let now_ms = self.started_at.elapsed().as_millis() as u64;
+        let delta_ms = now_ms.saturating_sub(self.last_event_ms);
+        self.last_event_ms = now_ms;
+
+        let delta_ticks = self.ms_to_ticks(delta_ms);
+        self.events.push((delta_ticks, channel, message));
+    }
+
+    /// Convert a millisecond delta to MIDI ticks given the recording's tempo
+    fn ms_to_ticks(&self, delta_ms: u64) -> u32 {
+        let us = delta_ms * 1000;
+        ((us * self.ticks_per_quarter as u64) / self.tempo_us_per_quarter as u64) as u32
+    }
+
+    /// Flush the captured events to a Standard MIDI File at `path`
+    pub fn flush(&self, path: &str) -> Result<()> {
+        use midly::{Header, Format, Timing, Track, TrackEvent, TrackEventKind};
+        use midly::num::{u15, u4, u28};
+
+        let mut track = Track::new();
+
+        track.push(TrackEvent {
+            delta: 0.into(),
+            kind: TrackEventKind::Meta(midly::MetaMessage::Tempo(
+                u28::from(self.tempo_us_per_quarter),
+            )),
+        });
+
+        for (delta, channel, message) in &self.events {
+            track.push(TrackEvent {
+                delta: u28::from(*delta),
+                kind: TrackEventKind::Midi {
+                    channel: u4::from(*channel),
+                    message: *message,
+                },
+            });
+        }
+
+        track.push(TrackEvent {
+            delta: 0.into(),
+            kind: TrackEventKind::Meta(midly::MetaMessage::EndOfTrack),
+        });
+
+        let smf = midly::Smf {
+            header: Header {
+                format: Format::SingleTrack,
+                timing: Timing::Metrical(u15::from(self.ticks_per_quarter)),
+            },
+            tracks: vec![track],
+        };
+
+        smf.save(path).context("Failed to write recorded MIDI file")?;
+        Ok(())
+    }
+}
+
+/// Buffers the post-effects PCM stream and flushes it to a WAV file on stop.
+pub struct WavRecording {
+    sample_rate: u32,
+    samples: Vec<f32>,
+}
+
+impl WavRecording {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Append a block of interleaved stereo samples captured after effects processing
+    pub fn push_block(&mut self, interleaved: &[f32]) {
+        self.samples.extend_from_slice(interleaved);
+    }
+
+    /// Flush the buffered PCM to a WAV file at `path`
+    pub fn flush(&self, path: &str) -> Result<()> {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut writer = hound::WavWriter::create(path, spec)
+            .context("Failed to create recorded WAV file")?;
+
+        for &sample in &self.samples {
+            let amplitude = (sample * 32767.0) as i16;
+            writer.write_sample(amplitude)?;
+        }
+
+        writer.finalize().context("Failed to finalize recorded WAV file")?;
+        Ok(())
+    }
+}
+
+/// Combines a `MidiRecording` and a `WavRecording` into a single take, started
+/// and stopped together from the live-play path.
+pub struct SessionRecorder {
+    midi: MidiRecording,
+    wav: WavRecording,
+    midi_out_path: String,
+    wav_out_path: String,
+}
+
+impl SessionRecorder {
+    pub fn new(
+        midi_out_path: &str,
+        wav_out_path: &str,
+        sample_rate: u32,
+        ticks_per_quarter: u16,
+        tempo_us_per_quarter: u32,
+    ) -> Self {
+        Self {
+            midi: MidiRecording::new(ticks_per_quarter, tempo_us_per_quarter),
+            wav: WavRecording::new(sample_rate),
+            midi_out_path: midi_out_path.to_string(),
+            wav_out_path: wav_out_path.to_string(),
+        }
+    }
+
+    pub fn push_midi_event(&mut self, channel: u8, message: MidiMessage) {
+        self.midi.push_event(channel, message);
+    }
+
+    pub fn push_audio_block(&mut self, interleaved: &[f32]) {
+        self.wav.push_block(interleaved);
+    }
+
+    /// Flush both the MIDI and WAV captures to disk
+    pub fn stop(&self) -> Result<()> {
+        self.midi.flush(&self.midi_out_path)?;
+        self.wav.flush(&self.wav_out_path)?;
+        Ok(())
+    }
+}
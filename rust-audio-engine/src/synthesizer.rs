@@ -8,6 +8,34 @@ use rustysynth::{SoundFont, Synthesizer, SynthesizerSettings};
 use std::fs::File;
 use std::sync::Arc;
 
+/// Expressive controls applied across an entire render pass: per-channel fine
+/// tuning, volume, and release-time shaping — analogous to progmidi's
+/// per-request `set_tune`/`set_volume`/`set_hold_time`/`set_falloff` knobs.
+#[derive(Debug, Clone)]
+pub struct ExpressionParams {
+    /// Global fine-tune offset in cents, applied to every channel via RPN 0,1
+    /// (Channel Fine Tuning). Positive sharpens, negative flattens; typical
+    /// alternate-temperament adjustments for gospel voicings stay within +/-50 cents.
+    pub tuning_cents: f32,
+    /// Per-channel volume multiplier (1.0 = unity, sent as MIDI CC7 value 127),
+    /// applied via the standard Channel Volume controller. Channels beyond the
+    /// slice keep unity volume.
+    pub channel_volumes: Vec<f32>,
+    /// Release/falloff amount in 0.0-1.0, applied via CC72 (Release Time).
+    /// 0.5 leaves the SoundFont's own release envelope unchanged.
+    pub release: f32,
+}
+
+impl Default for ExpressionParams {
+    fn default() -> Self {
+        Self {
+            tuning_cents: 0.0,
+            channel_volumes: Vec::new(),
+            release: 0.5,
+        }
+    }
+}
+
 pub struct MidiSynthesizer {
     synthesizer: Synthesizer,
     sample_rate: u32,
@@ -40,6 +68,19 @@ impl MidiSynthesizer {
 
     /// Synthesize a MIDI file to audio samples
     pub fn synthesize_file(&mut self, midi_path: &str) -> Result<Vec<f32>> {
+        self.synthesize_file_with_expression(midi_path, &ExpressionParams::default())
+    }
+
+    /// Synthesize a MIDI file to audio samples with expressive controls
+    /// (fine tuning, per-channel volume, release falloff) applied across the
+    /// whole render, on top of whatever the MIDI file itself encodes.
+    pub fn synthesize_file_with_expression(
+        &mut self,
+        midi_path: &str,
+        params: &ExpressionParams,
+    ) -> Result<Vec<f32>> {
+        self.apply_expression_params(params);
+
         // Parse MIDI file
         let midi_data = std::fs::read(midi_path)
             .context("Failed to read MIDI file")?;
@@ -137,6 +178,101 @@ impl MidiSynthesizer {
         Ok(interleaved)
     }
 
+    /// Parse a MIDI file into a flat sequence of `(time_seconds, midi_note)`
+    /// NoteOn events, ignoring everything else (controllers, pitch bend,
+    /// NoteOff). Used by performance scoring to build the "expected" note
+    /// sequence without actually rendering audio.
+    pub fn parse_note_events(midi_path: &str) -> Result<Vec<(f64, u8)>> {
+        let midi_data = std::fs::read(midi_path).context("Failed to read MIDI file")?;
+        let smf = Smf::parse(&midi_data).context("Failed to parse MIDI file")?;
+
+        let ticks_per_beat = match smf.header.timing {
+            midly::Timing::Metrical(tpb) => tpb.as_int() as f64,
+            midly::Timing::Timecode(fps, tpf) => (fps.as_f32() * tpf as f32) as f64,
+        };
+
+        let mut tempo = 500_000.0; // Default: 120 BPM
+        let mut notes = Vec::new();
+
+        for track in smf.tracks {
+            let mut current_tick = 0u64;
+
+            for event in track {
+                current_tick += event.delta.as_int() as u64;
+
+                match event.kind {
+                    TrackEventKind::Midi { message: MidiMessage::NoteOn { key, vel }, .. } if vel.as_int() > 0 => {
+                        let time_seconds =
+                            (current_tick as f64 * tempo) / (ticks_per_beat * 1_000_000.0);
+                        notes.push((time_seconds, key.as_int()));
+                    }
+                    TrackEventKind::Meta(midly::MetaMessage::Tempo(t)) => {
+                        tempo = t.as_int() as f64;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        notes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Ok(notes)
+    }
+
+    /// Render a block of audio from the synthesizer's current voice state
+    ///
+    /// Unlike `synthesize_file`, this does not advance through a parsed MIDI
+    /// timeline; it simply renders `frames` samples of whatever notes are
+    /// currently active. Used by the live-play path where MIDI events are
+    /// fed in as they arrive from a real input device.
+    pub fn render_block(&mut self, frames: usize) -> (Vec<f32>, Vec<f32>) {
+        let mut left = vec![0.0f32; frames];
+        let mut right = vec![0.0f32; frames];
+        self.synthesizer.render(&mut left, &mut right);
+        (left, right)
+    }
+
+    /// Sample rate this synthesizer was constructed with
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Feed a single real-time MIDI message (NoteOn/NoteOff/Controller/PitchBend)
+    /// into the underlying synthesizer. Public entry point for the live-play path.
+    pub fn handle_midi_message(&mut self, channel: u8, message: &MidiMessage) {
+        self.process_midi_message(channel, message);
+    }
+
+    /// Push a set of expressive controls out to all 16 MIDI channels before
+    /// synthesis begins, via the standard RPN/CC surface rustysynth already
+    /// understands (no need to touch the SoundFont or voice internals).
+    fn apply_expression_params(&mut self, params: &ExpressionParams) {
+        let tuning_14bit = (((params.tuning_cents.clamp(-100.0, 100.0) / 100.0) * 8192.0) + 8192.0) as i32;
+        let tuning_msb = (tuning_14bit >> 7) & 0x7F;
+        let tuning_lsb = tuning_14bit & 0x7F;
+        let release_value = (params.release.clamp(0.0, 1.0) * 127.0) as i32;
+
+        for channel in 0..16i32 {
+            let volume = params
+                .channel_volumes
+                .get(channel as usize)
+                .copied()
+                .unwrap_or(1.0);
+            let volume_value = (volume.clamp(0.0, 1.0) * 127.0) as i32;
+
+            // RPN 0,1: Channel Fine Tuning
+            self.synthesizer.process_midi_message(channel, 0xB0, 101, 0);
+            self.synthesizer.process_midi_message(channel, 0xB0, 100, 1);
+            self.synthesizer.process_midi_message(channel, 0xB0, 6, tuning_msb);
+            self.synthesizer.process_midi_message(channel, 0xB0, 38, tuning_lsb);
+            // Null RPN so any later incidental data-entry messages don't get misrouted
+            self.synthesizer.process_midi_message(channel, 0xB0, 101, 127);
+            self.synthesizer.process_midi_message(channel, 0xB0, 100, 127);
+
+            self.synthesizer.process_midi_message(channel, 0xB0, 7, volume_value); // Channel Volume
+            self.synthesizer.process_midi_message(channel, 0xB0, 72, release_value); // Release Time
+        }
+    }
+
     /// Process a single MIDI message
     fn process_midi_message(&mut self, channel: u8, message: &MidiMessage) {
         match message {
@@ -1,9 +1,244 @@
 //! Waveform visualization generator
 //!
-//! Generates PNG waveform images from audio files
+//! Generates PNG waveform images from audio files. WAV is read directly via
+//! `hound`; everything else (MP3, FLAC, OGG Vorbis, ...) is decoded via
+//! `symphonia` into the same interleaved `f32` shape, so the downsampling
+//! and rendering code below never needs to know which path a file took.
 
 use anyhow::{Context, Result};
 use hound::WavReader;
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// How each pixel column's sample window is reduced to a `(min, max)` pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingMethod {
+    /// Min/max of the window — looks dramatic, but exaggerates transients
+    /// relative to what the window actually sounds like
+    Peak,
+    /// `sqrt(mean(sample^2))` of the window, rendered symmetrically as
+    /// `(-rms, +rms)` — closer to perceived loudness
+    Rms,
+}
+
+/// How interleaved multichannel audio is turned into one or more waveform bands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelMode {
+    /// Average all interleaved channels per frame into a single waveform
+    Mono,
+    /// Render one horizontal band per channel, each `height / channels` pixels tall
+    Stacked,
+}
+
+/// Color/sampling configuration for `WaveformGenerator::render_png`
+#[derive(Debug, Clone)]
+pub struct WaveformConfig {
+    /// Foreground color for the waveform trace: a `"#RRGGBB"`/`"#RRGGBBAA"`
+    /// hex string, or the literal `"transparent"`
+    pub foreground: String,
+    /// Background fill color, same format as `foreground`
+    pub background: String,
+    /// Peak values are clamped to `[amp_min, amp_max]` before being scaled to
+    /// pixel rows, so callers can zoom into quiet passages or compress clipped ones
+    pub amp_min: f32,
+    pub amp_max: f32,
+    /// How each pixel column's samples are reduced to a `(min, max)` pair.
+    /// Defaults to `Peak` — it "looks cooler" even though `Rms` tracks
+    /// perceived loudness more closely.
+    pub sampling: SamplingMethod,
+    /// How multichannel audio is mixed or laid out. Defaults to `Mono`.
+    pub channel_mode: ChannelMode,
+}
+
+impl Default for WaveformConfig {
+    fn default() -> Self {
+        Self {
+            foreground: "#000000".to_string(),
+            background: "#FFFFFF".to_string(),
+            amp_min: -1.0,
+            amp_max: 1.0,
+            sampling: SamplingMethod::Peak,
+            channel_mode: ChannelMode::Mono,
+        }
+    }
+}
+
+/// Parse a `"#RRGGBB"`/`"#RRGGBBAA"` hex color string, or the literal
+/// `"transparent"`, into an RGBA pixel. Unrecognized input falls back to opaque black.
+fn parse_color(s: &str) -> Rgba<u8> {
+    if s.eq_ignore_ascii_case("transparent") {
+        return Rgba([0, 0, 0, 0]);
+    }
+
+    let hex = s.trim_start_matches('#');
+    let channel = |range: std::ops::Range<usize>| {
+        hex.get(range).and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+
+    match hex.len() {
+        6 => Rgba([
+            channel(0..2).unwrap_or(0),
+            channel(2..4).unwrap_or(0),
+            channel(4..6).unwrap_or(0),
+            255,
+        ]),
+        8 => Rgba([
+            channel(0..2).unwrap_or(0),
+            channel(2..4).unwrap_or(0),
+            channel(4..6).unwrap_or(0),
+            channel(6..8).unwrap_or(255),
+        ]),
+        _ => Rgba([0, 0, 0, 255]),
+    }
+}
+
+/// A sub-region of a file to render with `WaveformGenerator::generate_with_range`,
+/// selected either as seconds or raw sample indices. Resolved against the
+/// file's own sample rate and clamped to its length, so out-of-range bounds
+/// (e.g. a clip end past EOF) degrade gracefully instead of panicking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Range {
+    /// `(start_seconds, end_seconds)`
+    Seconds(f64, f64),
+    /// `(start_sample, end_sample)`, per mixed-down-to-mono (or single-channel) stream
+    Samples(usize, usize),
+}
+
+impl Range {
+    /// Resolve to a clamped `[start, end)` sample-index range
+    fn resolve(&self, sample_rate: u32, total_samples: usize) -> (usize, usize) {
+        let (start, end) = match *self {
+            Range::Seconds(start_s, end_s) => (
+                (start_s.max(0.0) * sample_rate as f64) as usize,
+                (end_s.max(0.0) * sample_rate as f64) as usize,
+            ),
+            Range::Samples(start, end) => (start, end),
+        };
+
+        let start = start.min(total_samples);
+        let end = end.min(total_samples).max(start);
+        (start, end)
+    }
+}
+
+/// Decode a file into one or more mono streams (per `channel_mode`), shared
+/// by `generate_with_config` and `generate_with_range` so both read and mix
+/// down audio the same way, regardless of source format
+fn decode_streams(audio_path: &str, channel_mode: ChannelMode) -> Result<(u32, Vec<Vec<f32>>)> {
+    let (sample_rate, channels, samples) = decode_interleaved(audio_path)?;
+
+    let streams: Vec<Vec<f32>> = match channel_mode {
+        ChannelMode::Mono => vec![mixdown_to_mono(&samples, channels)],
+        ChannelMode::Stacked => (0..channels.max(1)).map(|c| extract_channel(&samples, channels, c)).collect(),
+    };
+
+    Ok((sample_rate, streams))
+}
+
+/// Decode a file to `(sample_rate, channels, interleaved_samples)`. Dispatches
+/// on the file extension: `.wav` goes through `hound`, everything else
+/// through `symphonia`.
+fn decode_interleaved(audio_path: &str) -> Result<(u32, usize, Vec<f32>)> {
+    if is_wav_path(audio_path) {
+        decode_wav(audio_path)
+    } else {
+        decode_compressed(audio_path)
+    }
+}
+
+/// Whether a path's extension is `.wav` (case-insensitive) — everything else
+/// is routed through the `symphonia` decode path
+fn is_wav_path(audio_path: &str) -> bool {
+    Path::new(audio_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("wav"))
+        .unwrap_or(false)
+}
+
+fn decode_wav(audio_path: &str) -> Result<(u32, usize, Vec<f32>)> {
+    let mut reader = WavReader::open(audio_path).context("Failed to open audio file")?;
+    let spec = reader.spec();
+
+    let samples: Vec<f32> = if spec.sample_format == hound::SampleFormat::Float {
+        reader.samples::<f32>().map(|s| s.unwrap_or(0.0)).collect()
+    } else {
+        reader.samples::<i16>().map(|s| s.unwrap_or(0) as f32 / 32768.0).collect()
+    };
+
+    Ok((spec.sample_rate, spec.channels as usize, samples))
+}
+
+/// Decode a compressed file (MP3, FLAC, OGG Vorbis, ...) via `symphonia`,
+/// converting every decoded packet to interleaved `f32` with `SampleBuffer`
+/// so callers never have to deal with the source's native sample format
+fn decode_compressed(audio_path: &str) -> Result<(u32, usize, Vec<f32>)> {
+    let file = std::fs::File::open(audio_path).context("Failed to open audio file")?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = Path::new(audio_path).extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .context("Unrecognized or unsupported audio format")?;
+
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .context("No decodable audio track found")?
+        .clone();
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .context("Unsupported audio codec")?;
+
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate.context("Unknown sample rate")?;
+    let mut channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(1);
+
+    let mut interleaved = Vec::new();
+    let mut sample_buf: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(_)) | Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(e).context("Error reading audio packet"),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = *decoded.spec();
+                channels = spec.channels.count();
+
+                let buf = sample_buf
+                    .get_or_insert_with(|| SampleBuffer::<f32>::new(decoded.capacity() as u64, spec));
+                buf.copy_interleaved_ref(decoded);
+                interleaved.extend_from_slice(buf.samples());
+            }
+            Err(SymphoniaError::DecodeError(_)) => continue, // skip corrupt packet, keep going
+            Err(e) => return Err(e).context("Error decoding audio packet"),
+        }
+    }
+
+    Ok((sample_rate, channels, interleaved))
+}
 
 pub struct WaveformGenerator;
 
@@ -12,7 +247,8 @@ impl WaveformGenerator {
         Self
     }
 
-    /// Generate waveform PNG from audio file
+    /// Generate waveform PNG from audio file, using the default
+    /// black-on-white color scheme and a `[-1.0, 1.0]` amplitude range
     pub fn generate(
         &self,
         audio_path: &str,
@@ -20,47 +256,76 @@ impl WaveformGenerator {
         height: u32,
         _use_gpu: bool,
     ) -> Result<Vec<u8>> {
-        // Read audio file
-        let mut reader = WavReader::open(audio_path)
-            .context("Failed to open audio file")?;
-
-        let spec = reader.spec();
-        let samples: Vec<f32> = if spec.sample_format == hound::SampleFormat::Float {
-            reader.samples::<f32>()
-                .map(|s| s.unwrap_or(0.0))
-                .collect()
-        } else {
-            reader.samples::<i16>()
-                .map(|s| s.unwrap_or(0) as f32 / 32768.0)
-                .collect()
-        };
+        self.generate_with_config(audio_path, width, height, _use_gpu, &WaveformConfig::default())
+    }
 
-        // Downsample to width pixels
-        let peaks = self.downsample_to_peaks(&samples, width as usize, spec.channels as usize);
+    /// Generate waveform PNG from audio file with a custom color/amplitude config
+    pub fn generate_with_config(
+        &self,
+        audio_path: &str,
+        width: u32,
+        height: u32,
+        _use_gpu: bool,
+        config: &WaveformConfig,
+    ) -> Result<Vec<u8>> {
+        let (_, streams) = decode_streams(audio_path, config.channel_mode)?;
+
+        // Downsample each stream to width pixels
+        let bands: Vec<Vec<(f32, f32)>> = streams
+            .iter()
+            .map(|stream| match config.sampling {
+                SamplingMethod::Peak => self.downsample_to_peaks(stream, width as usize),
+                SamplingMethod::Rms => self.downsample_to_rms(stream, width as usize),
+            })
+            .collect();
 
         // Render to PNG
-        self.render_png(&peaks, width, height)
+        self.render_png(&bands, width, height, config)
     }
 
-    /// Downsample audio to peak values for each pixel
-    fn downsample_to_peaks(&self, samples: &[f32], width: usize, channels: usize) -> Vec<(f32, f32)> {
-        let mut peaks = Vec::with_capacity(width);
-        let samples_per_pixel = samples.len() / channels / width;
+    /// Generate a waveform PNG for only part of an audio file — a region
+    /// thumbnail or loop/clip preview — without pre-trimming it. `range` is
+    /// resolved against the file's sample rate and clamped to its length.
+    pub fn generate_with_range(
+        &self,
+        audio_path: &str,
+        width: u32,
+        height: u32,
+        _use_gpu: bool,
+        range: Range,
+        config: &WaveformConfig,
+    ) -> Result<Vec<u8>> {
+        let (sample_rate, streams) = decode_streams(audio_path, config.channel_mode)?;
+        let total_samples = streams.first().map(|s| s.len()).unwrap_or(0);
+        let (start, end) = range.resolve(sample_rate, total_samples);
+
+        let bands: Vec<Vec<(f32, f32)>> = streams
+            .iter()
+            .map(|stream| match config.sampling {
+                SamplingMethod::Peak => self.downsample_to_peaks(&stream[start..end], width as usize),
+                SamplingMethod::Rms => self.downsample_to_rms(&stream[start..end], width as usize),
+            })
+            .collect();
+
+        self.render_png(&bands, width, height, config)
+    }
+
+    /// Downsample a single mono stream to peak (min, max) values for each pixel
+    fn downsample_to_peaks(&self, samples: &[f32], width: usize) -> Vec<(f32, f32)> {
+        let samples_per_pixel = samples.len() / width.max(1);
 
         if samples_per_pixel == 0 {
             return vec![(0.0, 0.0); width];
         }
 
+        let mut peaks = Vec::with_capacity(width);
         for i in 0..width {
-            let start = i * samples_per_pixel * channels;
-            let end = ((i + 1) * samples_per_pixel * channels).min(samples.len());
+            let start = i * samples_per_pixel;
+            let end = ((i + 1) * samples_per_pixel).min(samples.len());
 
             let mut min = 0.0f32;
             let mut max = 0.0f32;
-
-            // Find min/max in this pixel's sample range
-            for j in (start..end).step_by(channels) {
-                let sample = samples[j];  // Just use left channel
+            for &sample in &samples[start..end] {
                 min = min.min(sample);
                 max = max.max(sample);
             }
@@ -71,19 +336,518 @@ impl WaveformGenerator {
         peaks
     }
 
-    /// Render peaks to PNG image
-    fn render_png(&self, peaks: &[(f32, f32)], width: u32, height: u32) -> Result<Vec<u8>> {
-        // Create simple PNG in memory
-        // For now, return a placeholder
-        // TODO: Use image crate or write simple PNG encoder
+    /// Downsample a single mono stream to `(-rms, +rms)` values for each
+    /// pixel, so the rendered waveform tracks perceived loudness rather than raw peaks
+    fn downsample_to_rms(&self, samples: &[f32], width: usize) -> Vec<(f32, f32)> {
+        let samples_per_pixel = samples.len() / width.max(1);
 
-        let mut png_data = Vec::new();
+        if samples_per_pixel == 0 {
+            return vec![(0.0, 0.0); width];
+        }
 
-        // PNG header
-        png_data.extend_from_slice(&[137, 80, 78, 71, 13, 10, 26, 10]);
+        let mut peaks = Vec::with_capacity(width);
+        for i in 0..width {
+            let start = i * samples_per_pixel;
+            let end = ((i + 1) * samples_per_pixel).min(samples.len());
+
+            let window = &samples[start..end];
+            let rms = if window.is_empty() {
+                0.0
+            } else {
+                (window.iter().map(|&s| s * s).sum::<f32>() / window.len() as f32).sqrt()
+            };
+            peaks.push((-rms, rms));
+        }
+
+        peaks
+    }
+
+    /// Render one or more waveform bands to a PNG image: a background-filled
+    /// canvas with each band's pixel columns getting a foreground-filled
+    /// vertical span between that column's (clamped) min and max peak,
+    /// centered within the band's own `height / bands.len()` pixel rows.
+    fn render_png(&self, bands: &[Vec<(f32, f32)>], width: u32, height: u32, config: &WaveformConfig) -> Result<Vec<u8>> {
+        let background = parse_color(&config.background);
+        let foreground = parse_color(&config.foreground);
+
+        let mut image = RgbaImage::from_pixel(width, height, background);
+        let num_bands = (bands.len() as u32).max(1);
+        let band_height = (height / num_bands).max(1);
+
+        for (band_index, peaks) in bands.iter().enumerate() {
+            let y_offset = band_index as u32 * band_height;
+            let this_band_height = if band_index as u32 + 1 == num_bands {
+                height.saturating_sub(y_offset).max(1) // last band absorbs any remainder
+            } else {
+                band_height
+            };
+
+            let half_height = this_band_height as f32 / 2.0;
+            let amp_range = (config.amp_max - config.amp_min).max(f32::EPSILON);
+            let y_for_amp = |amp: f32| -> u32 {
+                let clamped = amp.clamp(config.amp_min, config.amp_max);
+                // Rescale into [-1.0, 1.0] against the configured range before
+                // scaling by half_height, so amp_min/amp_max actually zoom
+                // instead of just clipping near the centerline.
+                let normalized = (clamped - config.amp_min) / amp_range * 2.0 - 1.0;
+                let local_y = (half_height - normalized * half_height)
+                    .round()
+                    .clamp(0.0, (this_band_height - 1) as f32) as u32;
+                y_offset + local_y
+            };
+
+            for (x, &(min, max)) in peaks.iter().enumerate().take(width as usize) {
+                let y_max = y_for_amp(max);
+                let y_min = y_for_amp(min);
+                let (y_start, y_end) = if y_max <= y_min { (y_max, y_min) } else { (y_min, y_max) };
+
+                for y in y_start..=y_end {
+                    if y < height {
+                        image.put_pixel(x as u32, y, foreground);
+                    }
+                }
+            }
+        }
+
+        let mut png_data = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+            .context("Failed to encode waveform PNG")?;
 
-        // For now, return a minimal valid PNG
-        // In production, use the `image` crate for proper PNG encoding
         Ok(png_data)
     }
 }
+
+/// Average all interleaved channels per frame into a single mono stream
+fn mixdown_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+
+    samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Extract a single interleaved channel as its own contiguous mono stream
+fn extract_channel(samples: &[f32], channels: usize, channel_index: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+
+    samples.iter().skip(channel_index).step_by(channels).copied().collect()
+}
+
+/// Decoded, mixed-down-to-mono audio, held in memory so it can drive several
+/// waveform renders without re-reading or re-decoding the source file
+pub struct AudioData {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl AudioData {
+    /// Decode a WAV file and mix it down to mono, the same way
+    /// `WaveformGenerator::generate` does with `ChannelMode::Mono`
+    pub fn from_wav_file(audio_path: &str) -> Result<Self> {
+        let (sample_rate, channels, samples) = decode_wav(audio_path)?;
+        Ok(Self {
+            samples: mixdown_to_mono(&samples, channels),
+            sample_rate,
+        })
+    }
+
+    /// Decode any file `WaveformGenerator` supports (WAV, or a compressed
+    /// format via `symphonia`) and mix it down to mono
+    pub fn from_file(audio_path: &str) -> Result<Self> {
+        let (sample_rate, channels, samples) = decode_interleaved(audio_path)?;
+        Ok(Self {
+            samples: mixdown_to_mono(&samples, channels),
+            sample_rate,
+        })
+    }
+}
+
+/// Min/max/sum-of-squares over one bin's worth of samples. Min/max merge by
+/// taking the wider extreme; sum-of-squares and count merge additively, which
+/// lets an RMS be recovered correctly from merged bins (unlike RMS values
+/// themselves, which don't average linearly).
+#[derive(Debug, Clone, Copy)]
+struct BinStats {
+    min: f32,
+    max: f32,
+    sum_sq: f64,
+    count: usize,
+}
+
+impl BinStats {
+    fn from_samples(samples: &[f32]) -> Self {
+        let mut min = 0.0f32;
+        let mut max = 0.0f32;
+        let mut sum_sq = 0.0f64;
+
+        for &sample in samples {
+            min = min.min(sample);
+            max = max.max(sample);
+            sum_sq += (sample as f64) * (sample as f64);
+        }
+
+        Self { min, max, sum_sq, count: samples.len() }
+    }
+
+    fn merge(a: &BinStats, b: &BinStats) -> Self {
+        Self {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+            sum_sq: a.sum_sq + b.sum_sq,
+            count: a.count + b.count,
+        }
+    }
+
+    fn rms(&self) -> f32 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.sum_sq / self.count as f64).sqrt() as f32
+        }
+    }
+
+    /// Reduce to the `(min, max)` pair `render_png` expects, per `SamplingMethod`
+    fn to_peak_pair(&self, sampling: SamplingMethod) -> (f32, f32) {
+        match sampling {
+            SamplingMethod::Peak => (self.min, self.max),
+            SamplingMethod::Rms => {
+                let rms = self.rms();
+                (-rms, rms)
+            }
+        }
+    }
+}
+
+/// Number of raw samples spanned by the finest precomputed bin level
+const BASE_BIN_SAMPLES: usize = 64;
+
+/// One precomputed mip level: `bins[i]` summarizes `bin_size` consecutive raw samples
+struct BinLevel {
+    bin_size: usize,
+    bins: Vec<BinStats>,
+}
+
+/// Precomputes min/max/sum-of-squares at several power-of-two bin sizes from
+/// an `AudioData`, so rendering the same audio at many pixel widths (zoom
+/// levels, thumbnails) doesn't have to rescan the raw samples each time.
+/// Each render instead aggregates the finest precomputed level that already
+/// has at least as many bins as the requested width.
+pub struct BinnedWaveformRenderer {
+    levels: Vec<BinLevel>,
+}
+
+impl BinnedWaveformRenderer {
+    /// Build the mip pyramid: a finest level of `BASE_BIN_SAMPLES`-sample
+    /// bins over the raw samples, then repeatedly merging adjacent bin pairs
+    /// (doubling `bin_size`) until a single bin remains
+    pub fn new(audio: &AudioData) -> Self {
+        let mut bin_size = BASE_BIN_SAMPLES.max(1);
+        let mut bins: Vec<BinStats> = audio
+            .samples
+            .chunks(bin_size)
+            .map(BinStats::from_samples)
+            .collect();
+
+        let mut levels = vec![BinLevel { bin_size, bins: bins.clone() }];
+
+        while bins.len() > 1 {
+            bin_size *= 2;
+            bins = bins
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => BinStats::merge(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(BinLevel { bin_size, bins: bins.clone() });
+        }
+
+        Self { levels }
+    }
+
+    /// Render a mono waveform PNG at `width` x `height`, aggregating the
+    /// finest precomputed level down to `width` bins instead of rescanning
+    /// the raw samples
+    pub fn render(&self, width: u32, height: u32, config: &WaveformConfig) -> Result<Vec<u8>> {
+        let bands = vec![self.peaks_for_width(width as usize, config.sampling)];
+        WaveformGenerator::new().render_png(&bands, width, height, config)
+    }
+
+    /// Pick the finest level with at least `width` bins, then aggregate its
+    /// bins down to exactly `width` entries (a level with fewer bins than
+    /// `width` has each bin repeated across neighboring entries)
+    fn peaks_for_width(&self, width: usize, sampling: SamplingMethod) -> Vec<(f32, f32)> {
+        let level = self
+            .levels
+            .iter()
+            .rev()
+            .find(|level| level.bins.len() >= width)
+            .unwrap_or_else(|| self.levels.first().expect("at least one level is always built"));
+
+        if width == 0 || level.bins.is_empty() {
+            return vec![(0.0, 0.0); width];
+        }
+
+        // Spread all of the level's bins over the width, so none past the
+        // last whole multiple of `width` are dropped
+        let num_bins = level.bins.len();
+        (0..width)
+            .map(|i| {
+                let start = (i * num_bins / width).min(num_bins - 1);
+                let end = ((i + 1) * num_bins / width).max(start + 1).min(num_bins);
+
+                level.bins[start..end]
+                    .iter()
+                    .copied()
+                    .reduce(|a, b| BinStats::merge(&a, &b))
+                    .map(|stats| stats.to_peak_pair(sampling))
+                    .unwrap_or((0.0, 0.0))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_hex_rgb() {
+        let c = parse_color("#FF8000");
+        assert_eq!(c, Rgba([0xFF, 0x80, 0x00, 255]));
+    }
+
+    #[test]
+    fn test_parse_color_hex_rgba() {
+        let c = parse_color("#FF800080");
+        assert_eq!(c, Rgba([0xFF, 0x80, 0x00, 0x80]));
+    }
+
+    #[test]
+    fn test_parse_color_transparent() {
+        assert_eq!(parse_color("transparent"), Rgba([0, 0, 0, 0]));
+        assert_eq!(parse_color("Transparent"), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_render_png_produces_valid_png_signature() {
+        let generator = WaveformGenerator::new();
+        let bands = vec![vec![(-0.5, 0.5), (-0.2, 0.8), (0.0, 0.0)]];
+
+        let png_data = generator.render_png(&bands, 3, 10, &WaveformConfig::default()).unwrap();
+
+        assert_eq!(&png_data[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+        assert!(png_data.len() > 8, "should contain more than just the signature");
+    }
+
+    #[test]
+    fn test_downsample_to_rms_is_symmetric_and_tracks_loudness() {
+        let generator = WaveformGenerator::new();
+        // Constant 0.5 amplitude signal: RMS should be exactly 0.5, drawn
+        // symmetrically as (-0.5, 0.5), where peak would give (0.0, 0.5)
+        let samples = vec![0.5f32; 1000];
+
+        let rms_peaks = generator.downsample_to_rms(&samples, 10);
+
+        for (min, max) in rms_peaks {
+            assert!((min - (-0.5)).abs() < 1e-4);
+            assert!((max - 0.5).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_downsample_to_rms_is_lower_than_peak_for_a_sine_wave() {
+        let generator = WaveformGenerator::new();
+        let samples: Vec<f32> = (0..4410)
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 44100.0).sin())
+            .collect();
+
+        let peak = generator.downsample_to_peaks(&samples, 1)[0];
+        let rms = generator.downsample_to_rms(&samples, 1)[0];
+
+        assert!(rms.1 < peak.1, "RMS of a sine wave should be lower than its peak");
+    }
+
+    #[test]
+    fn test_render_png_respects_transparent_background() {
+        let generator = WaveformGenerator::new();
+        let bands = vec![vec![(0.0, 0.0); 4]];
+        let config = WaveformConfig {
+            background: "transparent".to_string(),
+            ..Default::default()
+        };
+
+        let png_data = generator.render_png(&bands, 4, 4, &config).unwrap();
+        let decoded = image::load_from_memory(&png_data).unwrap().to_rgba8();
+
+        assert_eq!(decoded.get_pixel(0, 0)[3], 0, "background should be fully transparent");
+    }
+
+    #[test]
+    fn test_mixdown_to_mono_averages_stereo_frames() {
+        // Interleaved stereo: left = 1.0, right = -1.0 -> average 0.0
+        let samples = vec![1.0, -1.0, 0.5, -0.5];
+        let mono = mixdown_to_mono(&samples, 2);
+
+        assert_eq!(mono, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_extract_channel_pulls_out_a_single_channel() {
+        let samples = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]; // stereo: L=1,3,5 R=2,4,6
+        assert_eq!(extract_channel(&samples, 2, 0), vec![1.0, 3.0, 5.0]);
+        assert_eq!(extract_channel(&samples, 2, 1), vec![2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn test_render_png_stacked_bands_occupy_separate_vertical_regions() {
+        let generator = WaveformGenerator::new();
+        // Two channels, full-scale: each band should paint its own half of the image
+        let bands = vec![vec![(-1.0, 1.0); 2], vec![(-1.0, 1.0); 2]];
+        let config = WaveformConfig {
+            background: "transparent".to_string(),
+            foreground: "#000000".to_string(),
+            ..Default::default()
+        };
+
+        let png_data = generator.render_png(&bands, 2, 8, &config).unwrap();
+        let decoded = image::load_from_memory(&png_data).unwrap().to_rgba8();
+
+        // Every row across both bands should have been painted given full-scale amplitude
+        for y in 0..8 {
+            assert_eq!(decoded.get_pixel(0, y)[3], 255, "row {} should be painted by one of the two bands", y);
+        }
+    }
+
+    fn sine_audio(freq: f32, sample_rate: u32, num_samples: usize) -> AudioData {
+        let samples = (0..num_samples)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin())
+            .collect();
+        AudioData { samples, sample_rate }
+    }
+
+    #[test]
+    fn test_binned_renderer_builds_a_level_for_each_halving_until_one_bin_remains() {
+        let audio = sine_audio(440.0, 44100, BASE_BIN_SAMPLES * 8);
+        let renderer = BinnedWaveformRenderer::new(&audio);
+
+        assert_eq!(renderer.levels.first().unwrap().bin_size, BASE_BIN_SAMPLES);
+        assert_eq!(renderer.levels.last().unwrap().bins.len(), 1);
+        assert!(renderer.levels.windows(2).all(|w| w[1].bin_size == w[0].bin_size * 2));
+    }
+
+    #[test]
+    fn test_binned_renderer_peak_matches_direct_downsample() {
+        // Exactly `width` base-level bins worth of samples, so the finest
+        // level's bins line up one-to-one with the direct per-pixel windows
+        let width = 64;
+        let audio = sine_audio(220.0, 44100, width * BASE_BIN_SAMPLES);
+        let renderer = BinnedWaveformRenderer::new(&audio);
+        let generator = WaveformGenerator::new();
+
+        let direct = generator.downsample_to_peaks(&audio.samples, width);
+        let binned = renderer.peaks_for_width(width, SamplingMethod::Peak);
+
+        for ((d_min, d_max), (b_min, b_max)) in direct.iter().zip(binned.iter()) {
+            assert!((d_min - b_min).abs() < 1e-4);
+            assert!((d_max - b_max).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_binned_renderer_rms_tracks_loudness_not_peak() {
+        let audio = AudioData { samples: vec![0.5f32; 4096], sample_rate: 44100 };
+        let renderer = BinnedWaveformRenderer::new(&audio);
+
+        let rms_peaks = renderer.peaks_for_width(4, SamplingMethod::Rms);
+
+        for (min, max) in rms_peaks {
+            assert!((min - (-0.5)).abs() < 1e-4);
+            assert!((max - 0.5).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_binned_renderer_falls_back_to_finest_level_when_none_covers_the_width() {
+        let audio = sine_audio(330.0, 44100, BASE_BIN_SAMPLES * 100);
+        let renderer = BinnedWaveformRenderer::new(&audio);
+
+        // Requesting more bins than the finest level has should just fall back to it
+        let huge_width = renderer.levels.first().unwrap().bins.len() * 10;
+        let peaks = renderer.peaks_for_width(huge_width, SamplingMethod::Peak);
+
+        assert_eq!(peaks.len(), huge_width);
+    }
+
+    #[test]
+    fn test_binned_renderer_render_produces_valid_png() {
+        let audio = sine_audio(440.0, 44100, 44100);
+        let renderer = BinnedWaveformRenderer::new(&audio);
+
+        let png_data = renderer.render(200, 80, &WaveformConfig::default()).unwrap();
+
+        assert_eq!(&png_data[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+    }
+
+    #[test]
+    fn test_range_seconds_resolves_using_sample_rate() {
+        let (start, end) = Range::Seconds(0.5, 1.5).resolve(1000, 10_000);
+        assert_eq!((start, end), (500, 1500));
+    }
+
+    #[test]
+    fn test_range_samples_passes_through_unchanged() {
+        let (start, end) = Range::Samples(480_000, 720_000).resolve(44100, 1_000_000);
+        assert_eq!((start, end), (480_000, 720_000));
+    }
+
+    #[test]
+    fn test_range_clamps_to_buffer_length() {
+        let (start, end) = Range::Seconds(8.0, 20.0).resolve(1000, 10_000);
+        assert_eq!((start, end), (8_000, 10_000));
+    }
+
+    #[test]
+    fn test_range_clamps_end_before_start_to_start() {
+        // An inverted/garbage range shouldn't yield a negative-length slice
+        let (start, end) = Range::Samples(5000, 100).resolve(44100, 10_000);
+        assert_eq!((start, end), (5000, 5000));
+    }
+
+    #[test]
+    fn test_render_png_amp_range_zooms_quiet_passage_to_fill_canvas() {
+        let generator = WaveformGenerator::new();
+        // A quiet passage that only ever reaches +/-0.1 should still paint
+        // the full height of the canvas once amp_min/amp_max zoom into it.
+        let bands = vec![vec![(-0.1, 0.1)]];
+        let config = WaveformConfig {
+            background: "transparent".to_string(),
+            foreground: "#000000".to_string(),
+            amp_min: -0.1,
+            amp_max: 0.1,
+            ..Default::default()
+        };
+
+        let png_data = generator.render_png(&bands, 1, 8, &config).unwrap();
+        let decoded = image::load_from_memory(&png_data).unwrap().to_rgba8();
+
+        assert_eq!(decoded.get_pixel(0, 0)[3], 255, "top row should be painted once zoomed to the quiet range");
+        assert_eq!(decoded.get_pixel(0, 7)[3], 255, "bottom row should be painted once zoomed to the quiet range");
+    }
+
+    #[test]
+    fn test_is_wav_path_is_case_insensitive() {
+        assert!(is_wav_path("take1.wav"));
+        assert!(is_wav_path("take1.WAV"));
+        assert!(!is_wav_path("take1.mp3"));
+        assert!(!is_wav_path("take1.flac"));
+        assert!(!is_wav_path("take1"));
+    }
+}